@@ -1,7 +1,7 @@
 //! Rust Windows resource helper
 //!
 //! This crate implements a simple generator for Windows resource (.rc) files
-//! for use with either Microsoft `rc.exe` resource compiler or with GNU `windres.exe`
+//! for use with either Microsoft `rc.exe` resource compiler, LLVM `llvm-rc` or with GNU `windres.exe`
 //!
 //! The [`WindowsResorce::compile()`] method is intended to be used from a build script and
 //! needs environment variables from cargo to be set. It not only compiles the resource
@@ -19,7 +19,7 @@
 //! #      .set_output_directory(".")
 //!        .set("InternalName", "TEST.EXE")
 //!        // manually set version 1.0.0.0
-//!        .set_version_info(winres::VersionInfo::PRODUCTVERSION, 0x0001000000000000);
+//!        .set_version_info(winres::VersionInfo::PRODUCTVERSION, winres::pack_version(1, 0, 0, 0));
 //!     res.compile()?;
 //! }
 //! # Ok(())
@@ -36,7 +36,9 @@
 //! The following paths are the hardcoded defaults:
 //! MSVC the last registry key at
 //! `HKLM\SOFTWARE\Microsoft\Windows Kits\Installed Roots`, for MinGW we try our luck by simply
-//! using the `%PATH%` environment variable.
+//! using the `%PATH%` environment variable. With the `cc` feature `rc.exe` is first looked up
+//! like the `cc` crate looks up the MSVC tools, which also finds it in a Visual Studio
+//! developer environment.
 //!
 //! Note that the toolkit bitness as to match the one from the current Rust compiler. If you are
 //! using Rust GNU 64-bit you have to use MinGW64. For MSVC this is simpler as (recent) Windows
@@ -45,18 +47,27 @@
 //! [`WindowsResorce::compile()`]: struct.WindowsResource.html#method.compile
 //! [`WindowsResource::new()`]: struct.WindowsResource.html#method.new
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::env;
+use std::ffi::OsString;
 use std::fs;
 use std::io;
 use std::io::prelude::*;
 use std::path::{Path, PathBuf};
 use std::process;
 
+#[cfg(feature = "cc")]
+extern crate cc;
 extern crate toml;
 
 /// Version info field names
-#[derive(PartialEq, Eq, Hash, Debug)]
+///
+/// The fixed `VS_FIXEDFILEINFO` block also has the `dwFileDateMS` and `dwFileDateLS`
+/// fields, which have no statement in a `VERSIONINFO` resource. They are set with
+/// [`set_file_date()`] instead.
+///
+/// [`set_file_date()`]: struct.WindowsResource.html#method.set_file_date
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub enum VersionInfo {
     /// The version value consists of four 16 bit words, e.g.,
     /// `MAJOR << 48 | MINOR << 32 | PATCH << 16 | RELEASE`
@@ -77,27 +88,857 @@ pub enum VersionInfo {
     FILEFLAGS,
 }
 
+/// `FILEFLAGS`: the file contains debugging information
+pub const VS_FF_DEBUG: u64 = 0x01;
+/// `FILEFLAGS`: the file is a development version, not a released product
+pub const VS_FF_PRERELEASE: u64 = 0x02;
+/// `FILEFLAGS`: the file was modified and is not identical to the original
+pub const VS_FF_PATCHED: u64 = 0x04;
+/// `FILEFLAGS`: the file was not built with standard release procedures, the
+/// `"PrivateBuild"` string should be set
+pub const VS_FF_PRIVATEBUILD: u64 = 0x08;
+/// `FILEFLAGS`: the version info was created dynamically
+pub const VS_FF_INFOINFERRED: u64 = 0x10;
+/// `FILEFLAGS`: the file is a variation of the normal file, the `"SpecialBuild"`
+/// string should be set
+pub const VS_FF_SPECIALBUILD: u64 = 0x20;
+/// `FILEFLAGSMASK`: all of the `VS_FF_*` flags are valid
+pub const VS_FFI_FILEFLAGSMASK: u64 = 0x3f;
+
+/// `FILEOS`: the operating system is unknown
+pub const VOS_UNKNOWN: u64 = 0x00000;
+/// `FILEOS`: designed for MS-DOS
+pub const VOS_DOS: u64 = 0x10000;
+/// `FILEOS`: designed for Windows NT
+pub const VOS_NT: u64 = 0x40000;
+/// `FILEOS`: designed for the Win32 API, combined with `VOS_DOS` or `VOS_NT`
+pub const VOS__WINDOWS32: u64 = 0x00004;
+/// `FILEOS`: designed for the Win32 API on Windows 95/98/Me
+pub const VOS_DOS_WINDOWS32: u64 = VOS_DOS | VOS__WINDOWS32;
+/// `FILEOS`: designed for the Win32 API on Windows NT and later (default)
+pub const VOS_NT_WINDOWS32: u64 = VOS_NT | VOS__WINDOWS32;
+
+/// Pack four 16 bit version words into the `u64` used by [`FILEVERSION`] and
+/// [`PRODUCTVERSION`], i.e., `MAJOR << 48 | MINOR << 32 | PATCH << 16 | BUILD`
+///
+/// ```rust
+/// assert_eq!(winres::pack_version(1, 2, 3, 4), 0x0001000200030004);
+/// ```
+///
+/// [`FILEVERSION`]: enum.VersionInfo.html#variant.FILEVERSION
+/// [`PRODUCTVERSION`]: enum.VersionInfo.html#variant.PRODUCTVERSION
+pub fn pack_version(major: u16, minor: u16, patch: u16, build: u16) -> u64 {
+    (major as u64) << 48 | (minor as u64) << 32 | (patch as u64) << 16 | build as u64
+}
+
+/// Split a packed version value into its four 16 bit words
+/// `(major, minor, patch, build)`. This is the inverse of [`pack_version()`].
+///
+/// [`pack_version()`]: fn.pack_version.html
+pub fn unpack_version(version: u64) -> (u16, u16, u16, u16) {
+    (
+        (version >> 48) as u16,
+        (version >> 32) as u16,
+        (version >> 16) as u16,
+        version as u16,
+    )
+}
+
+/// Severity of a message reported by the resource compiler
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+/// A message of the resource compiler, parsed from its console output
+///
+/// Both `rc.exe` (`file(line) : error RC2135 : message`) and `windres`
+/// (`file:line: warning: message`) output is understood. When the location
+/// cannot be parsed, `file` and `line` are `None`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+}
+
+/// The result of [`compile_silent()`]
+///
+/// [`compile_silent()`]: struct.WindowsResource.html#method.compile_silent
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CompileOutput {
+    /// The compiled resource, `None` if nothing was compiled for a non-Windows target
+    pub artifact: Option<PathBuf>,
+    /// The cargo directives [`compile()`] would print, e.g.,
+    /// `cargo:rustc-link-lib=static=resource`
+    ///
+    /// [`compile()`]: struct.WindowsResource.html#method.compile
+    pub directives: Vec<String>,
+    /// The warnings and notes of the resource compiler
+    pub diagnostics: Vec<Diagnostic>,
+    /// The standard output of the resource compiler, which is only printed with
+    /// `LogLevel::Verbose`, see [`set_log_level()`]
+    ///
+    /// [`set_log_level()`]: struct.WindowsResource.html#method.set_log_level
+    pub stdout: String,
+    /// The standard error output of the resource compiler
+    pub stderr: String,
+}
+
+/// The kind of a [`ResolvedCompiler`]
+///
+/// [`ResolvedCompiler`]: struct.ResolvedCompiler.html
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CompilerKind {
+    /// `rc.exe` of the Windows SDK
+    Rc,
+    /// `windres` of the GNU toolkit
+    Windres,
+    /// `llvm-rc` of the LLVM toolchain
+    LlvmRc,
+}
+
+/// The resource compiler [`compile()`] uses, see [`resolve_compiler()`]
+///
+/// [`compile()`]: struct.WindowsResource.html#method.compile
+/// [`resolve_compiler()`]: struct.WindowsResource.html#method.resolve_compiler
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ResolvedCompiler {
+    pub kind: CompilerKind,
+    /// The path of the executable, looked up in `PATH` if it is not in the toolkit
+    pub path: PathBuf,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(f, "{}({}): ", file, line)?,
+            (Some(file), None) => write!(f, "{}: ", file)?,
+            _ => {}
+        }
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        };
+        write!(f, "{}: {}", severity, self.message)
+    }
+}
+
+/// The error returned by [`compile()`]
+///
+/// There is a `From` conversion in both directions from and to `io::Error`, so `?`
+/// works in build scripts that return `io::Result`.
+///
+/// [`compile()`]: struct.WindowsResource.html#method.compile
+#[derive(Debug)]
+pub enum WinresError {
+    /// The resource compiler could not be found, e.g., no Windows SDK is installed
+    ToolkitNotFound(String),
+    /// The resource compiler returned an error, or reported warnings with
+    /// [`set_warnings_as_errors()`]
+    ///
+    /// [`set_warnings_as_errors()`]: struct.WindowsResource.html#method.set_warnings_as_errors
+    Compiler {
+        status: process::ExitStatus,
+        stdout: String,
+        stderr: String,
+        /// The messages parsed from `stdout` and `stderr`
+        diagnostics: Vec<Diagnostic>,
+    },
+    /// `ar` could not create the static library of the GNU toolkit
+    Archiver {
+        status: process::ExitStatus,
+        stderr: String,
+    },
+    /// Icon, bitmap or cursor files that are missing or not in the expected format, or
+    /// missing files of other resources, with the problem found
+    InvalidResourceFiles(Vec<(PathBuf, String)>),
+    /// Reading or writing a file failed, or a setting is invalid
+    Io(io::Error),
+    /// Resources cannot be compiled for the target, e.g., `x86_64-unknown-linux-gnu`
+    UnsupportedTarget(String),
+    /// An environment variable that cargo sets for build scripts is missing
+    MissingEnvVar(String),
+    /// Resources of [`compile_all()`] that failed, with their output name
+    ///
+    /// [`compile_all()`]: struct.WindowsResource.html#method.compile_all
+    Failed(Vec<(String, WinresError)>),
+}
+
+impl std::fmt::Display for WinresError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            WinresError::ToolkitNotFound(msg) => f.write_str(msg),
+            WinresError::Compiler {
+                status,
+                stdout,
+                stderr,
+                diagnostics,
+            } => {
+                if status.success() {
+                    f.write_str("Resource compiler reported warnings, which are treated as errors")?;
+                } else {
+                    write!(f, "Could not compile resource file ({})", status)?;
+                }
+                if diagnostics.is_empty() {
+                    // show the raw output, when it could not be parsed
+                    for text in &[stdout, stderr] {
+                        if !text.trim().is_empty() {
+                            write!(f, "\n{}", text.trim_end())?;
+                        }
+                    }
+                }
+                for d in diagnostics {
+                    write!(f, "\n{}", d)?;
+                }
+                Ok(())
+            }
+            WinresError::Archiver { status, stderr } => {
+                write!(
+                    f,
+                    "Could not create static library for resource file ({})",
+                    status
+                )?;
+                if !stderr.trim().is_empty() {
+                    write!(f, "\n{}", stderr.trim_end())?;
+                }
+                Ok(())
+            }
+            WinresError::InvalidResourceFiles(files) => {
+                f.write_str("Invalid resource files:")?;
+                for (path, problem) in files {
+                    write!(f, "\n{}: {}", path.display(), problem)?;
+                }
+                Ok(())
+            }
+            WinresError::Io(e) => e.fmt(f),
+            WinresError::UnsupportedTarget(target) => write!(
+                f,
+                "Resources can only be compiled for Windows with target_env \"gnu\" or \"msvc\", not for {}",
+                target
+            ),
+            WinresError::MissingEnvVar(name) => write!(
+                f,
+                "{} not set; winres must be called from a build script",
+                name
+            ),
+            WinresError::Failed(errors) => {
+                write!(f, "{} resources failed to compile:", errors.len())?;
+                for (name, e) in errors {
+                    write!(f, "\n{}: {}", name, e)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl WinresError {
+    /// The error for a failed run of the resource compiler
+    fn compiler(output: process::Output, diagnostics: Vec<Diagnostic>) -> Self {
+        WinresError::Compiler {
+            status: output.status,
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            diagnostics,
+        }
+    }
+}
+
+impl std::error::Error for WinresError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WinresError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for WinresError {
+    fn from(e: io::Error) -> Self {
+        WinresError::Io(e)
+    }
+}
+
+impl From<WinresError> for io::Error {
+    fn from(e: WinresError) -> Self {
+        match e {
+            WinresError::Io(e) => e,
+            WinresError::ToolkitNotFound(_) => {
+                io::Error::new(io::ErrorKind::NotFound, e.to_string())
+            }
+            WinresError::InvalidResourceFiles(_) => {
+                io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+            }
+            _ => io::Error::other(e.to_string()),
+        }
+    }
+}
+
+/// The kind of crate the resource is compiled for, see [`set_crate_type()`]
+///
+/// [`set_crate_type()`]: struct.WindowsResource.html#method.set_crate_type
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CrateType {
+    /// A `bin` crate (default)
+    Executable,
+    /// A `cdylib` or `dylib` crate
+    DynamicLibrary,
+    /// A `staticlib` crate that is linked into a C or C++ application
+    StaticLibrary,
+}
+
+/// How much winres prints to the build script output, see [`set_log_level()`]
+///
+/// [`set_log_level()`]: struct.WindowsResource.html#method.set_log_level
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum LogLevel {
+    /// Only the cargo directives that are required for linking
+    Quiet,
+    /// Problems, compiler messages and the selected toolkit (default)
+    Normal,
+    /// Everything, including the full compiler output and how paths were resolved
+    Verbose,
+}
+
+/// How the package version is written to the `"FileVersion"` and `"ProductVersion"`
+/// strings, see [`set_version_string_format()`]
+///
+/// [`set_version_string_format()`]: struct.WindowsResource.html#method.set_version_string_format
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VersionStringFormat {
+    /// The full semantic version, e.g., `1.2.3-beta.4+build567` (default)
+    Full,
+    /// Only `major.minor.patch`, e.g., `1.2.3`
+    Short,
+    /// Four numbers like the numeric version, e.g., `1.2.3.4`, where the last number
+    /// is taken from the pre-release, see [`set_version_string_format()`]
+    ///
+    /// [`set_version_string_format()`]: struct.WindowsResource.html#method.set_version_string_format
+    Numeric,
+}
+
+/// How paths are written into the `cargo:rustc-link-*` directives
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LinkPathStyle {
+    /// Use the output directory as it was given (default)
+    Native,
+    /// Replace backslashes with forward slashes, which every linker accepts
+    ForwardSlashes,
+}
+
+/// The resource compiler for the MSVC target, see [`set_resource_compiler()`]
+///
+/// [`set_resource_compiler()`]: struct.WindowsResource.html#method.set_resource_compiler
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ResourceCompiler {
+    /// `rc.exe` of the Windows SDK, or `llvm-rc` if `rc.exe` cannot be found (default)
+    Auto,
+    /// `rc.exe` of the Windows SDK
+    Rc,
+    /// `llvm-rc` of the LLVM toolchain, e.g., for cross compiling from Linux
+    LlvmRc,
+}
+
+/// The encoding of the generated resource file, see [`set_rc_encoding()`]
+///
+/// [`set_rc_encoding()`]: struct.WindowsResource.html#method.set_rc_encoding
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RcEncoding {
+    /// UTF-8 with `#pragma code_page(65001)` (default)
+    Utf8,
+    /// UTF-16LE with a byte order mark, without the pragma
+    Utf16Le,
+}
+
+/// How the GNU toolkit links the compiled resource, see [`set_gnu_link_mode()`]
+///
+/// [`set_gnu_link_mode()`]: struct.WindowsResource.html#method.set_gnu_link_mode
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GnuLinkMode {
+    /// Wrap the object file in `lib<name>.a` with `ar` and link it with
+    /// `cargo:rustc-link-lib=static=<name>` (default)
+    StaticArchive,
+    /// Pass the object file to the linker with `cargo:rustc-link-arg=`, `ar` is not run
+    DirectObject,
+}
+
+/// DPI awareness of an application, see [`set_dpi_awareness()`]
+///
+/// [`set_dpi_awareness()`]: struct.WindowsResource.html#method.set_dpi_awareness
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DpiAwareness {
+    /// The application is bitmap scaled by Windows
+    Unaware,
+    /// The application scales itself to the DPI of the primary monitor
+    System,
+    /// The application scales itself to the DPI of the monitor it is shown on
+    PerMonitor,
+    /// Like `PerMonitor`, but Windows also scales non-client areas and dialogs
+    PerMonitorV2,
+}
+
+impl DpiAwareness {
+    fn name(self) -> &'static str {
+        match self {
+            DpiAwareness::Unaware => "unaware",
+            DpiAwareness::System => "system",
+            DpiAwareness::PerMonitor => "permonitor",
+            DpiAwareness::PerMonitorV2 => "permonitorv2",
+        }
+    }
+
+    /// The value of the `<dpiAwareness>` element (Windows 10 1607 and later),
+    /// a list that falls back to the next entry when a value is not supported
+    fn dpi_awareness_value(self) -> &'static str {
+        match self {
+            DpiAwareness::Unaware => "Unaware",
+            DpiAwareness::System => "System",
+            DpiAwareness::PerMonitor => "PerMonitor",
+            DpiAwareness::PerMonitorV2 => "PerMonitorV2,PerMonitor",
+        }
+    }
+
+    /// The value of the legacy `<dpiAware>` element, which is ignored when
+    /// `<dpiAwareness>` is understood
+    fn dpi_aware_value(self) -> &'static str {
+        match self {
+            DpiAwareness::Unaware => "false",
+            DpiAwareness::System => "true",
+            DpiAwareness::PerMonitor | DpiAwareness::PerMonitorV2 => "true/pm",
+        }
+    }
+}
+
+/// The privileges an application requests from UAC, see [`set_requested_execution_level()`]
+///
+/// [`set_requested_execution_level()`]: struct.WindowsResource.html#method.set_requested_execution_level
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExecutionLevel {
+    /// Run with the privileges of the parent process
+    AsInvoker,
+    /// Run with the highest privileges the user can obtain, administrators are asked
+    HighestAvailable,
+    /// Always ask for administrator privileges
+    RequireAdministrator,
+}
+
+impl ExecutionLevel {
+    /// The value of the `level` attribute of `<requestedExecutionLevel>`
+    fn value(self) -> &'static str {
+        match self {
+            ExecutionLevel::AsInvoker => "asInvoker",
+            ExecutionLevel::HighestAvailable => "highestAvailable",
+            ExecutionLevel::RequireAdministrator => "requireAdministrator",
+        }
+    }
+}
+
+/// The code page of the `-A` Windows API functions, see [`set_active_code_page()`]
+///
+/// [`set_active_code_page()`]: struct.WindowsResource.html#method.set_active_code_page
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ActiveCodePage {
+    /// UTF-8 (Windows 10 1903 and later)
+    Utf8,
+    /// The code page of the system locale, which is also the default
+    Legacy,
+}
+
+impl ActiveCodePage {
+    /// The value of the `<activeCodePage>` element
+    fn value(self) -> &'static str {
+        match self {
+            ActiveCodePage::Utf8 => "UTF-8",
+            ActiveCodePage::Legacy => "Legacy",
+        }
+    }
+}
+
+/// The content of a manifest, see [`add_manifest()`]
+///
+/// [`add_manifest()`]: struct.WindowsResource.html#method.add_manifest
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ManifestSource {
+    /// The manifest XML, included in the resource file
+    Inline(String),
+    /// The path of a manifest file, which is included by the resource compiler
+    File(String),
+}
+
+/// The key of an [`AcceleratorEntry`]
+///
+/// [`AcceleratorEntry`]: struct.AcceleratorEntry.html
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AcceleratorKey {
+    /// A virtual key code, e.g., `0x70` for `VK_F1` or `0x41` for the `A` key
+    VirtKey(u16),
+    /// The character the key produces, e.g., `'a'`, which is case sensitive
+    Char(char),
+}
+
+/// An entry of an accelerator table, see [`add_accelerator()`]
+///
+/// `shift` and `alt` are only valid for [`AcceleratorKey::VirtKey`]. For a character
+/// key `control` is written as `"^C"` and requires an ASCII letter.
+///
+/// [`add_accelerator()`]: struct.WindowsResource.html#method.add_accelerator
+/// [`AcceleratorKey::VirtKey`]: enum.AcceleratorKey.html#variant.VirtKey
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct AcceleratorEntry {
+    pub key: AcceleratorKey,
+    /// The command ID sent with `WM_COMMAND`
+    pub command: u16,
+    pub control: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl AcceleratorEntry {
+    /// Check the modifiers, see the type documentation
+    fn check(&self) -> Result<(), &'static str> {
+        match self.key {
+            AcceleratorKey::Char(_) if self.shift => Err("SHIFT requires a virtual key"),
+            AcceleratorKey::Char(_) if self.alt => Err("ALT requires a virtual key"),
+            AcceleratorKey::Char(c) if self.control && !c.is_ascii_alphabetic() => {
+                Err("CONTROL requires a virtual key or an ASCII letter")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The line of the `ACCELERATORS` block
+    fn to_rc(self) -> String {
+        let mut line = match self.key {
+            AcceleratorKey::VirtKey(vk) => format!("{:#04x}, {}, VIRTKEY", vk, self.command),
+            AcceleratorKey::Char(c) if self.control => {
+                format!("\"^{}\", {}, ASCII", c.to_ascii_uppercase(), self.command)
+            }
+            // quotes, backslashes and carets cannot be written as a one character
+            // string, the character code works for any character
+            AcceleratorKey::Char(c) if c.is_ascii_graphic() && !"\"\\^".contains(c) => {
+                format!("\"{}\", {}, ASCII", c, self.command)
+            }
+            AcceleratorKey::Char(c) => format!("{}, {}, ASCII", c as u32, self.command),
+        };
+        if let AcceleratorKey::VirtKey(_) = self.key {
+            if self.control {
+                line.push_str(", CONTROL");
+            }
+            if self.shift {
+                line.push_str(", SHIFT");
+            }
+            if self.alt {
+                line.push_str(", ALT");
+            }
+        }
+        line
+    }
+}
+
+/// An element of the `<windowsSettings>` node in the application manifest
+#[derive(Debug)]
+struct WindowsSetting {
+    name: &'static str,
+    namespace: &'static str,
+    value: String,
+}
+
+/// A `<dependentAssembly>` in the application manifest, see [`add_dependent_assembly()`]
+///
+/// [`add_dependent_assembly()`]: struct.WindowsResource.html#method.add_dependent_assembly
+#[derive(Debug)]
+struct DependentAssembly {
+    name: String,
+    version: String,
+    public_key_token: String,
+}
+
+/// The string properties of one language, see [`add_language_strings()`]
+///
+/// [`add_language_strings()`]: struct.WindowsResource.html#method.add_language_strings
+#[derive(Debug)]
+struct LanguageStrings {
+    language: u16,
+    codepage: u16,
+    properties: HashMap<String, String>,
+}
+
+/// A function that edits the generated resource file, see [`set_rc_transform()`]
+///
+/// [`set_rc_transform()`]: struct.WindowsResource.html#method.set_rc_transform
+struct RcTransform(Box<dyn Fn(String) -> String + Send + Sync>);
+
+impl std::fmt::Debug for RcTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("RcTransform")
+    }
+}
+
+/// The type of a resource included from a file, see [`add_resource()`]
+///
+/// [`add_resource()`]: struct.WindowsResource.html#method.add_resource
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ResourceType {
+    /// `RT_RCDATA (10)`, raw data
+    RcData,
+    /// `RT_HTML (23)`, HTML, scripts and style sheets, e.g., for `res://` URLs
+    Html,
+    /// `RT_MANIFEST (24)`, a side-by-side assembly manifest
+    Manifest,
+    /// `"TYPELIB"`, a type library, which `LoadTypeLib` looks up by this type name
+    TypeLib,
+    /// Any other numeric type ID
+    Custom(u16),
+}
+
+impl ResourceType {
+    /// The type as written in the resource file
+    fn rc_type(self) -> String {
+        match self {
+            ResourceType::RcData => "RCDATA".to_string(),
+            ResourceType::Html => "HTML".to_string(),
+            ResourceType::Manifest => "24".to_string(),
+            ResourceType::TypeLib => "TYPELIB".to_string(),
+            ResourceType::Custom(id) => id.to_string(),
+        }
+    }
+}
+
+/// A resource that is included from a file, e.g., `LICENSE RCDATA "LICENSE.txt"`
+#[derive(Debug)]
+struct CustomResource {
+    name_id: String,
+    type_id: String,
+    path: String,
+}
+
+/// The string properties of the version info that are known to Windows
+const KNOWN_PROPERTIES: &[&str] = &[
+    "Comments",
+    "CompanyName",
+    "FileDescription",
+    "FileVersion",
+    "InternalName",
+    "LegalCopyright",
+    "LegalTrademarks",
+    "OriginalFilename",
+    "PrivateBuild",
+    "ProductName",
+    "ProductVersion",
+    "SpecialBuild",
+    // set by embed_license()
+    "License",
+];
+
+/// Language tags and their LANGID, see [`set_language_str()`]
+///
+/// [`set_language_str()`]: struct.WindowsResource.html#method.set_language_str
+const LANGUAGE_TAGS: &[(&str, u16)] = &[
+    ("ar-SA", 0x0401),
+    ("br-FR", 0x047e),
+    ("ca-ES", 0x0403),
+    ("cs-CZ", 0x0405),
+    ("da-DK", 0x0406),
+    ("de-AT", 0x0c07),
+    ("de-CH", 0x0807),
+    ("de-DE", 0x0407),
+    ("el-GR", 0x0408),
+    ("en-AU", 0x0c09),
+    ("en-CA", 0x1009),
+    ("en-GB", 0x0809),
+    ("en-IE", 0x1809),
+    ("en-IN", 0x4009),
+    ("en-NZ", 0x1409),
+    ("en-US", 0x0409),
+    ("es-ES", 0x0c0a),
+    ("es-MX", 0x080a),
+    ("eu-ES", 0x042d),
+    ("fi-FI", 0x040b),
+    ("fr-BE", 0x080c),
+    ("fr-CA", 0x0c0c),
+    ("fr-CH", 0x100c),
+    ("fr-FR", 0x040c),
+    ("gd-GB", 0x0491),
+    ("he-IL", 0x040d),
+    ("hi-IN", 0x0439),
+    ("hu-HU", 0x040e),
+    ("id-ID", 0x0421),
+    ("it-CH", 0x0810),
+    ("it-IT", 0x0410),
+    ("ja-JP", 0x0411),
+    ("ko-KR", 0x0412),
+    ("nb-NO", 0x0414),
+    ("nl-BE", 0x0813),
+    ("nl-NL", 0x0413),
+    ("nn-NO", 0x0814),
+    ("pl-PL", 0x0415),
+    ("pt-BR", 0x0416),
+    ("pt-PT", 0x0816),
+    ("rm-CH", 0x0417),
+    ("ro-RO", 0x0418),
+    ("ru-RU", 0x0419),
+    ("sk-SK", 0x041b),
+    ("sv-SE", 0x041d),
+    ("th-TH", 0x041e),
+    ("tr-TR", 0x041f),
+    ("uk-UA", 0x0422),
+    ("vi-VN", 0x042a),
+    ("zh-CN", 0x0804),
+    ("zh-HK", 0x0c04),
+    ("zh-TW", 0x0404),
+];
+
+/// The name ID of the `RCDATA` resource with the license text, see [`embed_license()`]
+///
+/// [`embed_license()`]: struct.WindowsResource.html#method.embed_license
+pub const LICENSE_RESOURCE: &str = "LICENSE";
+
+/// The name ID of the `RCDATA` resource with the AppUserModelID, see
+/// [`set_app_user_model_id()`]
+///
+/// [`set_app_user_model_id()`]: struct.WindowsResource.html#method.set_app_user_model_id
+pub const APP_USER_MODEL_ID_RESOURCE: &str = "APP_USER_MODEL_ID";
+
+/// An icon, bitmap or cursor file
 #[derive(Debug)]
-struct Icon {
+struct Image {
     path: String,
     name_id: String,
 }
 
+/// A Windows SDK, detected once and shared by several resources
+///
+/// [`WindowsResource::new()`] looks up the SDK in the registry every time it is called.
+/// Build scripts that compile more than one resource can detect it once with
+/// [`Sdk::detect()`] and pass it to [`WindowsResource::with_sdk()`] or
+/// [`set_sdk()`], which also caches the include directories.
+///
+/// [`WindowsResource::new()`]: struct.WindowsResource.html#method.new
+/// [`Sdk::detect()`]: #method.detect
+/// [`WindowsResource::with_sdk()`]: struct.WindowsResource.html#method.with_sdk
+/// [`set_sdk()`]: struct.WindowsResource.html#method.set_sdk
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Sdk {
+    bin: PathBuf,
+    include_roots: Vec<PathBuf>,
+}
+
+impl Sdk {
+    /// Find the newest Windows SDK in the registry
+    ///
+    /// With the `cc` feature `rc.exe` is located with `cc::windows_registry` first.
+    pub fn detect() -> io::Result<Sdk> {
+        let bin = match find_rc_with_cc() {
+            Some(bin) => bin,
+            None => get_sdk()?.pop().unwrap(),
+        };
+        Ok(Sdk::from_path(bin))
+    }
+
+    /// Find a specific version of the Windows SDK in the registry, e.g., `10.0.19041.0`
+    ///
+    /// Unlike [`detect()`] this never uses `cc`. It is an error if the version is
+    /// not installed; the message lists the versions that are.
+    ///
+    /// [`detect()`]: #method.detect
+    pub fn detect_version(version: &str) -> io::Result<Sdk> {
+        select_sdk_version(get_sdk()?, version).map(Sdk::from_path)
+    }
+
+    /// Use the SDK with `rc.exe` in the directory `bin`, e.g.,
+    /// `C:\Program Files (x86)\Windows Kits\10\bin\10.0.17763.0\x64`
+    pub fn from_path<P: AsRef<Path>>(bin: P) -> Sdk {
+        let bin = bin.as_ref().to_path_buf();
+        let include_roots = win_sdk_include_dirs(&bin.join("rc.exe"));
+        Sdk { bin, include_roots }
+    }
+
+    /// The path of the resource compiler
+    pub fn rc_exe(&self) -> PathBuf {
+        self.bin.join("rc.exe")
+    }
+
+    /// The include directories with the SDK headers, e.g., `um` and `shared`
+    pub fn include_roots(&self) -> &[PathBuf] {
+        &self.include_roots
+    }
+
+    /// The version of a Windows 10 or later SDK, e.g., `10.0.17763.0`
+    ///
+    /// Older SDKs are not versioned by directory and return `None`.
+    pub fn version(&self) -> Option<&str> {
+        let mut iter = self.bin.iter();
+        iter.find(|p| *p == "bin");
+        iter.next()
+            .and_then(|v| v.to_str())
+            .filter(|v| v.starts_with("10."))
+    }
+}
+
 #[derive(Debug)]
 pub struct WindowsResource {
     toolkit_path: PathBuf,
+    toolkit_candidates: Vec<PathBuf>,
+    sdk: Option<Sdk>,
+    sdk_error: Option<String>,
     properties: HashMap<String, String>,
     version_info: HashMap<VersionInfo, u64>,
     rc_file: Option<String>,
-    icons: Vec<Icon>,
+    input_files: Vec<PathBuf>,
+    icons: Vec<Image>,
+    primary_icon: Option<String>,
+    bitmaps: Vec<Image>,
+    cursors: Vec<Image>,
+    accelerators: Vec<(String, Vec<AcceleratorEntry>)>,
+    strings: Vec<(u16, String)>,
     language: u16,
-    manifest: Option<String>,
-    manifest_file: Option<String>,
+    codepage: u16,
+    language_strings: Vec<LanguageStrings>,
+    manifests: Vec<(Option<u16>, ManifestSource)>,
     output_directory: String,
     windres_path: String,
     ar_path: String,
+    llvm_rc_path: String,
+    resource_compiler: ResourceCompiler,
     add_toolkit_include: bool,
     append_rc_content: String,
+    thin_archive: bool,
+    keep_res: bool,
+    prefer_native_res: bool,
+    gnu_link_mode: GnuLinkMode,
+    link_path_style: LinkPathStyle,
+    windows_settings: Vec<WindowsSetting>,
+    output_name: String,
+    dpi_awareness: Option<DpiAwareness>,
+    execution_level: Option<ExecutionLevel>,
+    dependent_assemblies: Vec<DependentAssembly>,
+    dpi_marker: Option<String>,
+    app_user_model_id: Option<String>,
+    emit_version_info: bool,
+    file_date: Option<u64>,
+    include_paths_front: Vec<PathBuf>,
+    include_paths: Vec<PathBuf>,
+    crate_type: CrateType,
+    resources: Vec<CustomResource>,
+    strict: bool,
+    skip_non_windows: bool,
+    metadata_errors: Vec<String>,
+    dry_run: bool,
+    incremental: bool,
+    warnings_as_errors: bool,
+    force_emit: HashSet<String>,
+    manifest_id: Option<u16>,
+    manifest_type: u16,
+    manifest_external: bool,
+    log_level: LogLevel,
+    log_messages: Vec<(LogLevel, String)>,
+    rc_transform: Option<RcTransform>,
+    rc_encoding: RcEncoding,
 }
 
 #[allow(clippy::new_without_default)]
@@ -116,8 +957,11 @@ impl WindowsResource {
     ///
     /// Furthermore if a section `package.metadata.winres` exists
     /// in `Cargo.toml` it will be parsed. Values in this section take precedence
-    /// over the values provided natively by cargo. Only the string table
-    /// of the version struct can be set this way.
+    /// over the values provided natively by cargo. String values set the string
+    /// table of the version struct. The upper case keys `FILEVERSION`,
+    /// `PRODUCTVERSION`, `FILEOS`, `FILETYPE`, `FILESUBTYPE`, `FILEFLAGSMASK` and
+    /// `FILEFLAGS` set the [`VersionInfo`] field of the same name instead, to an
+    /// integer or, for the two versions, to a string like `"1.2.3.4"`.
     /// Additionally, the language field is set to neutral (i.e. `0`)
     /// and no icon is set. These settings have to be done programmatically.
     ///
@@ -130,6 +974,8 @@ impl WindowsResource {
     /// OriginalFilename = "testing.exe"
     /// FileDescription = "⛄❤☕"
     /// LegalCopyright = "Copyright © 2016"
+    /// FILEVERSION = "1.2.3.4"
+    /// FILEFLAGS = 0x8
     /// ```
     ///
     /// The version info struct is set to some values
@@ -145,89 +991,201 @@ impl WindowsResource {
     /// | `FILEFLAGSMASK`      | `VS_FFI_FILEFLAGSMASK (0x3F)`|
     /// | `FILEFLAGS`          | `0x0`                        |
     ///
+    /// The last word of `FILEVERSION` and `PRODUCTVERSION` is the last number of the
+    /// pre-release, e.g., `4` for `1.2.3-rc.4`. It is `0` for versions without a
+    /// pre-release, a pre-release without a number like `1.2.3-beta`, or a number
+    /// that does not fit into 16 bits; use [`set_version_build()`] to set it instead.
+    ///
+    /// [`set_version_build()`]: #method.set_version_build
+    /// [`VersionInfo`]: enum.VersionInfo.html
     pub fn new() -> Self {
+        WindowsResource::try_new().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Create a new resource like [`new()`], but return an error instead of panicking
+    ///
+    /// [`new()`] needs the environment variables cargo sets for a build script, e.g.,
+    /// `CARGO_PKG_VERSION` and `CARGO_MANIFEST_DIR`. This returns
+    /// [`WinresError::MissingEnvVar`] when one of them is not set, e.g., when
+    /// called from a test or a `main.rs`.
+    ///
+    /// [`new()`]: #method.new
+    /// [`WinresError::MissingEnvVar`]: enum.WinresError.html#variant.MissingEnvVar
+    pub fn try_new() -> Result<Self, WinresError> {
+        // messages are printed by compile(), when the log level is known
+        let mut log = Vec::new();
+        let mut sdk_error = None;
+        let sdk = if cfg!(target_env = "msvc") {
+            if let Some(bin) = find_rc_with_cc() {
+                log.push((
+                    LogLevel::Verbose,
+                    format!("Found rc.exe with cc: {}", bin.display()),
+                ));
+                bin
+            } else {
+                match get_sdk() {
+                    Ok(mut v) => {
+                        for kit in &v {
+                            log.push((
+                                LogLevel::Verbose,
+                                format!("Found Windows SDK: {}", kit.display()),
+                            ));
+                        }
+                        v.pop().unwrap()
+                    }
+                    Err(e) => {
+                        log.push((LogLevel::Verbose, format!("No Windows SDK found: {}", e)));
+                        sdk_error = Some(e.to_string());
+                        PathBuf::new()
+                    }
+                }
+            }
+        } else if cfg!(windows) {
+            PathBuf::from("\\")
+        } else {
+            PathBuf::from("/")
+        };
+        let mut res = WindowsResource::with_toolkit(sdk, log)?;
+        res.sdk_error = sdk_error;
+        Ok(res)
+    }
+
+    /// Create a new resource like [`new()`], but use an already detected SDK
+    ///
+    /// This skips the lookup of the SDK in the registry, see [`Sdk`].
+    ///
+    /// [`new()`]: #method.new
+    /// [`Sdk`]: struct.Sdk.html
+    pub fn with_sdk(sdk: &Sdk) -> Self {
+        let mut res = WindowsResource::with_toolkit(PathBuf::new(), Vec::new())
+            .unwrap_or_else(|e| panic!("{}", e));
+        res.set_sdk(sdk);
+        res
+    }
+
+    fn with_toolkit(
+        toolkit_path: PathBuf,
+        mut log: Vec<(LogLevel, String)>,
+    ) -> Result<Self, WinresError> {
         let mut props: HashMap<String, String> = HashMap::new();
         let mut ver: HashMap<VersionInfo, u64> = HashMap::new();
 
-        props.insert(
-            "FileVersion".to_string(),
-            env::var("CARGO_PKG_VERSION").unwrap(),
-        );
+        props.insert("FileVersion".to_string(), cargo_env("CARGO_PKG_VERSION")?);
         props.insert(
             "ProductVersion".to_string(),
-            env::var("CARGO_PKG_VERSION").unwrap(),
-        );
-        props.insert(
-            "ProductName".to_string(),
-            env::var("CARGO_PKG_NAME").unwrap(),
-        );
-        props.insert(
-            "FileDescription".to_string(),
-            env::var("CARGO_PKG_NAME").unwrap(),
+            cargo_env("CARGO_PKG_VERSION")?,
         );
+        props.insert("ProductName".to_string(), cargo_env("CARGO_PKG_NAME")?);
+        props.insert("FileDescription".to_string(), cargo_env("CARGO_PKG_NAME")?);
 
-        parse_cargo_toml(&mut props).unwrap();
-
-        let mut version = 0_u64;
-        version |= env::var("CARGO_PKG_VERSION_MAJOR")
-            .unwrap()
-            .parse()
-            .unwrap_or(0)
-            << 48;
-        version |= env::var("CARGO_PKG_VERSION_MINOR")
-            .unwrap()
-            .parse()
-            .unwrap_or(0)
-            << 32;
-        version |= env::var("CARGO_PKG_VERSION_PATCH")
-            .unwrap()
-            .parse()
-            .unwrap_or(0)
-            << 16;
-        // version |= env::var("CARGO_PKG_VERSION_PRE").unwrap().parse().unwrap_or(0);
+        let version = pack_version(
+            cargo_env("CARGO_PKG_VERSION_MAJOR")?.parse().unwrap_or(0),
+            cargo_env("CARGO_PKG_VERSION_MINOR")?.parse().unwrap_or(0),
+            cargo_env("CARGO_PKG_VERSION_PATCH")?.parse().unwrap_or(0),
+            prerelease_number(&env::var("CARGO_PKG_VERSION_PRE").unwrap_or_default()),
+        );
         ver.insert(VersionInfo::FILEVERSION, version);
         ver.insert(VersionInfo::PRODUCTVERSION, version);
-        ver.insert(VersionInfo::FILEOS, 0x00040004);
+        ver.insert(VersionInfo::FILEOS, VOS_NT_WINDOWS32);
         ver.insert(VersionInfo::FILETYPE, 1);
         ver.insert(VersionInfo::FILESUBTYPE, 0);
-        ver.insert(VersionInfo::FILEFLAGSMASK, 0x3F);
+        ver.insert(VersionInfo::FILEFLAGSMASK, VS_FFI_FILEFLAGSMASK);
         ver.insert(VersionInfo::FILEFLAGS, 0);
 
-        let sdk = if cfg!(target_env = "msvc") {
-            match get_sdk() {
-                Ok(mut v) => v.pop().unwrap(),
-                Err(_) => PathBuf::new(),
-            }
-        } else if cfg!(windows) {
-            PathBuf::from("\\")
-        } else {
-            PathBuf::from("/")
-        };
+        let mut metadata_errors = Vec::new();
+        parse_cargo_toml(&mut props, &mut ver, &mut log, &mut metadata_errors)?;
+        for e in &metadata_errors {
+            log.push((LogLevel::Normal, e.clone()));
+        }
 
-        WindowsResource {
-            toolkit_path: sdk,
+        Ok(WindowsResource {
+            toolkit_path,
+            toolkit_candidates: Vec::new(),
+            sdk: None,
+            sdk_error: None,
             properties: props,
             version_info: ver,
             rc_file: None,
+            input_files: Vec::new(),
             icons: Vec::new(),
+            primary_icon: None,
+            bitmaps: Vec::new(),
+            cursors: Vec::new(),
+            accelerators: Vec::new(),
+            strings: Vec::new(),
             language: 0,
-            manifest: None,
-            manifest_file: None,
+            codepage: 0x04b0,
+            language_strings: Vec::new(),
+            manifests: Vec::new(),
             output_directory: env::var("OUT_DIR").unwrap_or_else(|_| ".".to_string()),
 
             #[cfg(windows)]
             windres_path: "windres.exe".to_string(),
             #[cfg(unix)]
-            windres_path: "windres".to_string(),
+            windres_path: find_gnu_tool("windres"),
 
             #[cfg(windows)]
             ar_path: "ar.exe".to_string(),
             #[cfg(unix)]
-            ar_path: "ar".to_string(),
+            ar_path: find_gnu_tool("ar"),
+
+            llvm_rc_path: "llvm-rc".to_string(),
+            resource_compiler: ResourceCompiler::Auto,
 
             add_toolkit_include: false,
             append_rc_content: String::new(),
-        }
+            thin_archive: false,
+            keep_res: false,
+            prefer_native_res: false,
+            gnu_link_mode: GnuLinkMode::StaticArchive,
+            link_path_style: LinkPathStyle::Native,
+            windows_settings: Vec::new(),
+            output_name: "resource".to_string(),
+            dpi_awareness: None,
+            execution_level: None,
+            dependent_assemblies: Vec::new(),
+            dpi_marker: None,
+            app_user_model_id: None,
+            emit_version_info: true,
+            file_date: None,
+            include_paths_front: Vec::new(),
+            include_paths: Vec::new(),
+            crate_type: CrateType::Executable,
+            resources: Vec::new(),
+            strict: false,
+            skip_non_windows: false,
+            metadata_errors,
+            dry_run: false,
+            incremental: true,
+            warnings_as_errors: false,
+            force_emit: HashSet::new(),
+            manifest_id: None,
+            manifest_type: 24,
+            manifest_external: false,
+            log_level: LogLevel::Normal,
+            log_messages: log,
+            rc_transform: None,
+            rc_encoding: RcEncoding::Utf8,
+        })
+    }
+
+    /// Create a resource that only contains an icon.
+    ///
+    /// No `VERSIONINFO` resource is written, which results in the smallest possible
+    /// resource for tools that only want an icon in the Explorer. More resources can
+    /// still be added to the returned value.
+    ///
+    /// ```rust,no_run
+    /// # extern crate winres;
+    /// # fn main() -> std::io::Result<()> {
+    /// winres::WindowsResource::icon_only("icon.ico").compile()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn icon_only(path: &str) -> Self {
+        let mut res = WindowsResource::new();
+        res.set_version_info_enabled(false).set_icon(path);
+        res
     }
 
     /// Set string properties of the version info struct.
@@ -240,7 +1198,7 @@ impl WindowsResource {
     ///  - `"ProductName"`
     ///  - `"OriginalFilename"`
     ///  - `"LegalCopyright"`
-    ///  - `"LegalTrademark"`
+    ///  - `"LegalTrademarks"`
     ///  - `"CompanyName"`
     ///  - `"Comments"`
     ///  - `"InternalName"`
@@ -248,48 +1206,451 @@ impl WindowsResource {
     /// Additionally there exists
     /// `"PrivateBuild"`, `"SpecialBuild"`
     /// which should only be set, when the `FILEFLAGS` property is set to
-    /// `VS_FF_PRIVATEBUILD(0x08)` or `VS_FF_SPECIALBUILD(0x20)`
+    /// `VS_FF_PRIVATEBUILD(0x08)` or `VS_FF_SPECIALBUILD(0x20)`, which
+    /// [`set_private_build()`] and [`set_special_build()`] do for you.
     ///
     /// It is possible to use arbirtrary field names but Windows Explorer and other
-    /// tools might not show them.
+    /// tools might not show them. Use [`set_strict()`] to reject unknown names.
+    ///
+    /// [`set_strict()`]: #method.set_strict
+    /// [`set_private_build()`]: #method.set_private_build
+    /// [`set_special_build()`]: #method.set_special_build
     pub fn set<'a>(&mut self, name: &'a str, value: &'a str) -> &mut Self {
         self.properties.insert(name.to_string(), value.to_string());
         self
     }
 
-    /// Set the correct path for the toolkit.
+    /// The value of a string property, as set by [`new()`] or [`set()`]
     ///
-    /// For the GNU toolkit this has to be the path where MinGW
-    /// put `windres.exe` and `ar.exe`. This could be something like:
-    /// `"C:\Program Files\mingw-w64\x86_64-5.3.0-win32-seh-rt_v4-rev0\mingw64\bin"`
+    /// [`new()`]: #method.new
+    /// [`set()`]: #method.set
+    pub fn property(&self, name: &str) -> Option<&str> {
+        self.properties.get(name).map(String::as_str)
+    }
+
+    /// Write a string property even if its value is empty.
     ///
-    /// For MSVC the Windows SDK has to be installed. It comes with the resource compiler
-    /// `rc.exe`. This should be set to the root directory of the Windows SDK, e.g.,
-    /// `"C:\Program Files (x86)\Windows Kits\10"`
-    /// or, if multiple 10 versions are installed,
-    /// set it directly to the corret bin directory
-    /// `"C:\Program Files (x86)\Windows Kits\10\bin\10.0.14393.0\x64"`
+    /// Properties with an empty value are skipped by default, as Windows does not
+    /// show them. Some tools distinguish between a missing and an empty value; for
+    /// the properties marked with this function `VALUE "name", ""` is written, even
+    /// if they were never [`set()`].
     ///
-    /// If it is left unset, it will look up a path in the registry,
-    /// i.e. `HKLM\SOFTWARE\Microsoft\Windows Kits\Installed Roots`
-    pub fn set_toolkit_path<'a>(&mut self, path: &'a str) -> &mut Self {
-        self.toolkit_path = PathBuf::from(path);
+    /// [`set()`]: #method.set
+    pub fn force_emit_property(&mut self, name: &str) -> &mut Self {
+        self.force_emit.insert(name.to_string());
         self
     }
 
-    /// Set the user interface language of the file
+    /// Set the `"ProductVersion"` string, e.g., a marketing version like `"2024 Edition"`
     ///
-    /// # Example
+    /// Only the string shown by the Explorer is changed, the numeric `PRODUCTVERSION`
+    /// stays at the version from `Cargo.toml` or the value set with
+    /// [`set_version_info()`]. Both are initialized to the package version by [`new()`].
     ///
-    /// ```
-    /// extern crate winapi;
-    /// extern crate winres;
-    /// # use std::io;
-    /// fn main() {
-    ///   if cfg!(target_os = "windows") {
-    ///     let mut res = winres::WindowsResource::new();
-    /// #   res.set_output_directory(".");
-    ///     res.set_language(winapi::um::winnt::MAKELANGID(
+    /// [`set_version_info()`]: #method.set_version_info
+    /// [`new()`]: #method.new
+    pub fn set_product_version_string(&mut self, version: &str) -> &mut Self {
+        self.set("ProductVersion", version)
+    }
+
+    /// Set how the package version is written to the version strings.
+    ///
+    /// [`new()`] sets `"FileVersion"` and `"ProductVersion"` to the full
+    /// `package.version`, including pre-release and build metadata, which might be
+    /// undesirable in the Explorer. This function sets both strings from the package
+    /// version again, formatted as `format`.
+    ///
+    /// For [`VersionStringFormat::Numeric`] the build word of the numeric `FILEVERSION`
+    /// and `PRODUCTVERSION` is set to the last number of the pre-release, e.g., `4`
+    /// for `1.2.3-beta.4`, so strings and numbers match; it is `0` for versions
+    /// without a numeric pre-release. Build metadata is never used, as it must not
+    /// affect the version order.
+    ///
+    /// Call this before [`set()`] or [`set_version_info()`], as it overwrites them.
+    ///
+    /// [`new()`]: #method.new
+    /// [`VersionStringFormat::Numeric`]: enum.VersionStringFormat.html#variant.Numeric
+    /// [`set()`]: #method.set
+    /// [`set_version_info()`]: #method.set_version_info
+    pub fn set_version_string_format(&mut self, format: VersionStringFormat) -> &mut Self {
+        let part = |name: &str| {
+            env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0)
+        };
+        let (major, minor, patch) = (
+            part("CARGO_PKG_VERSION_MAJOR"),
+            part("CARGO_PKG_VERSION_MINOR"),
+            part("CARGO_PKG_VERSION_PATCH"),
+        );
+        let version = match format {
+            VersionStringFormat::Full => env::var("CARGO_PKG_VERSION").unwrap_or_default(),
+            VersionStringFormat::Short => format!("{}.{}.{}", major, minor, patch),
+            VersionStringFormat::Numeric => {
+                let build =
+                    prerelease_number(&env::var("CARGO_PKG_VERSION_PRE").unwrap_or_default());
+                let version = pack_version(major, minor, patch, build);
+                self.set_version_info(VersionInfo::FILEVERSION, version)
+                    .set_version_info(VersionInfo::PRODUCTVERSION, version);
+                format!("{}.{}.{}.{}", major, minor, patch, build)
+            }
+        };
+        self.set("FileVersion", &version)
+            .set("ProductVersion", &version)
+    }
+
+    /// Set the last word of the numeric `FILEVERSION` and `PRODUCTVERSION`.
+    ///
+    /// [`new()`] takes it from the pre-release of the package version, this overrides
+    /// it, e.g., with a CI build number. The strings `"FileVersion"` and
+    /// `"ProductVersion"` are not changed.
+    ///
+    /// [`new()`]: #method.new
+    pub fn set_version_build(&mut self, build: u16) -> &mut Self {
+        for field in &[VersionInfo::FILEVERSION, VersionInfo::PRODUCTVERSION] {
+            if let Some(version) = self.version_info.get_mut(field) {
+                *version = *version & !0xffff | u64::from(build);
+            }
+        }
+        self
+    }
+
+    /// Set `FILEFLAGS`, e.g., to `winres::VS_FF_PRERELEASE | winres::VS_FF_PRIVATEBUILD`.
+    ///
+    /// Combine the `VS_FF_*` constants with `|`. Only flags in `FILEFLAGSMASK` are
+    /// read by Windows, which are all of them by default.
+    pub fn set_file_flags(&mut self, flags: u64) -> &mut Self {
+        self.set_version_info(VersionInfo::FILEFLAGS, flags)
+    }
+
+    /// Set the `"PrivateBuild"` string and the `VS_FF_PRIVATEBUILD` flag.
+    ///
+    /// Windows only shows the string when the flag is set in `FILEFLAGS`, so this
+    /// adds [`VS_FF_PRIVATEBUILD`] to the flags, keeping the others. The text should
+    /// say by whom and where the private build was made.
+    ///
+    /// [`VS_FF_PRIVATEBUILD`]: constant.VS_FF_PRIVATEBUILD.html
+    pub fn set_private_build(&mut self, text: &str) -> &mut Self {
+        let flags = self.version_info(&VersionInfo::FILEFLAGS).unwrap_or(0);
+        self.set("PrivateBuild", text)
+            .set_file_flags(flags | VS_FF_PRIVATEBUILD)
+    }
+
+    /// Set the `"SpecialBuild"` string and the `VS_FF_SPECIALBUILD` flag.
+    ///
+    /// Like [`set_private_build()`] for [`VS_FF_SPECIALBUILD`]. The text should say
+    /// how this build differs from the normal one.
+    ///
+    /// [`set_private_build()`]: #method.set_private_build
+    /// [`VS_FF_SPECIALBUILD`]: constant.VS_FF_SPECIALBUILD.html
+    pub fn set_special_build(&mut self, text: &str) -> &mut Self {
+        let flags = self.version_info(&VersionInfo::FILEFLAGS).unwrap_or(0);
+        self.set("SpecialBuild", text)
+            .set_file_flags(flags | VS_FF_SPECIALBUILD)
+    }
+
+    /// Set the numeric `FILEVERSION` from its four words.
+    ///
+    /// This is `set_version_info(VersionInfo::FILEVERSION, pack_version(...))`. Set
+    /// `PRODUCTVERSION` with [`set_version_info()`] and [`pack_version()`] if it
+    /// should differ from the package version as well. The string `"FileVersion"`
+    /// is not changed.
+    ///
+    /// [`set_version_info()`]: #method.set_version_info
+    /// [`pack_version()`]: fn.pack_version.html
+    pub fn set_file_version_parts(
+        &mut self,
+        major: u16,
+        minor: u16,
+        patch: u16,
+        build: u16,
+    ) -> &mut Self {
+        self.set_version_info(
+            VersionInfo::FILEVERSION,
+            pack_version(major, minor, patch, build),
+        )
+    }
+
+    /// Set the numeric `FILEVERSION` from a string like `"1.2.3.4"`.
+    ///
+    /// Missing numbers are `0`, e.g., `"1.2"` is `1.2.0.0`. More than four numbers,
+    /// or numbers that are not in `0..=65535`, are an `InvalidInput` error. See
+    /// [`set_file_version_parts()`].
+    ///
+    /// [`set_file_version_parts()`]: #method.set_file_version_parts
+    pub fn set_file_version_str(&mut self, version: &str) -> io::Result<&mut Self> {
+        let version =
+            parse_version(version).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        Ok(self.set_version_info(VersionInfo::FILEVERSION, version))
+    }
+
+    /// Reject unknown string property names and non-Windows targets.
+    ///
+    /// In strict mode [`validate()`], and thus [`compile()`], return an error for
+    /// properties that are not in the list shown for [`set()`], or `"License"` as set
+    /// by [`embed_license()`]. This catches typos like `"ProdcutName"`, which would
+    /// never show up in the Explorer. The default is to accept any name.
+    ///
+    /// Also, [`compile()`] returns an error when the target OS is not Windows, instead
+    /// of skipping the compilation with a warning, and when `Cargo.toml` cannot be
+    /// parsed or `package.metadata.winres` contains values that cannot be used, see
+    /// [`new()`], instead of ignoring them with a message.
+    ///
+    /// [`validate()`]: #method.validate
+    /// [`compile()`]: #method.compile
+    /// [`set()`]: #method.set
+    /// [`embed_license()`]: #method.embed_license
+    /// [`new()`]: #method.new
+    pub fn set_strict(&mut self, strict: bool) -> &mut Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Silently skip the compilation for targets that are not Windows.
+    ///
+    /// With `true`, [`compile()`] returns `Ok(())` when the target OS is not Windows,
+    /// without writing any file or printing a warning or link directive, even in
+    /// [`set_strict()`] mode. This lets build scripts call [`compile()`] without
+    /// a `cfg!(target_os = "windows")` check. By default the compilation is skipped
+    /// with a warning, or fails in strict mode.
+    ///
+    /// [`compile()`]: #method.compile
+    /// [`set_strict()`]: #method.set_strict
+    pub fn set_skip_on_non_windows(&mut self, skip: bool) -> &mut Self {
+        self.skip_non_windows = skip;
+        self
+    }
+
+    /// Whether [`compile()`] skips the target OS, see [`set_skip_on_non_windows()`]
+    ///
+    /// [`compile()`]: #method.compile
+    /// [`set_skip_on_non_windows()`]: #method.set_skip_on_non_windows
+    fn skip_target_os(&self, target_os: &str) -> Result<bool, WinresError> {
+        if target_os.is_empty() || target_os == "windows" {
+            return Ok(false);
+        }
+        let msg = format!(
+            "Resources can only be compiled for Windows, not for target_os \"{}\"",
+            target_os
+        );
+        if self.skip_non_windows {
+            self.log(LogLevel::Verbose, &format!("{}, skipping", msg));
+            return Ok(true);
+        }
+        if self.strict {
+            return Err(WinresError::UnsupportedTarget(target_name(target_os)));
+        }
+        self.log(
+            LogLevel::Normal,
+            &format!("cargo:warning={}, skipping", msg),
+        );
+        Ok(true)
+    }
+
+    /// Check the settings before compiling.
+    ///
+    /// This is called by [`compile()`]; see [`set_strict()`] for what is checked.
+    ///
+    /// [`compile()`]: #method.compile
+    /// [`set_strict()`]: #method.set_strict
+    pub fn validate(&self) -> io::Result<()> {
+        if self.strict {
+            let mut unknown: Vec<&str> = self
+                .properties
+                .keys()
+                .chain(
+                    self.language_strings
+                        .iter()
+                        .flat_map(|l| l.properties.keys()),
+                )
+                .chain(self.force_emit.iter())
+                .map(String::as_str)
+                .filter(|k| !KNOWN_PROPERTIES.contains(k))
+                .collect();
+            if !unknown.is_empty() {
+                unknown.sort();
+                unknown.dedup();
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Unknown version info properties: {}", unknown.join(", ")),
+                ));
+            }
+            if !self.metadata_errors.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "Invalid metadata in Cargo.toml: {}",
+                        self.metadata_errors.join(", ")
+                    ),
+                ));
+            }
+        }
+        let mut ids: Vec<u16> = self.strings.iter().map(|(id, _)| *id).collect();
+        ids.sort_unstable();
+        let mut duplicates: Vec<String> = ids
+            .windows(2)
+            .filter(|w| w[0] == w[1])
+            .map(|w| w[0].to_string())
+            .collect();
+        if !duplicates.is_empty() {
+            duplicates.dedup();
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Duplicate string table IDs: {}", duplicates.join(", ")),
+            ));
+        }
+        for &(images, kind) in &[
+            (&self.icons, "icon"),
+            (&self.bitmaps, "bitmap"),
+            (&self.cursors, "cursor"),
+        ] {
+            for (i, image) in images.iter().enumerate() {
+                if images[..i]
+                    .iter()
+                    .any(|other| other.name_id == image.name_id)
+                {
+                    continue;
+                }
+                let paths: Vec<&str> = images[i..]
+                    .iter()
+                    .filter(|other| other.name_id == image.name_id)
+                    .map(|other| other.path.as_str())
+                    .collect();
+                if paths.len() > 1 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!(
+                            "Duplicate {} name ID '{}' (paths {})",
+                            kind,
+                            image.name_id,
+                            paths.join(", ")
+                        ),
+                    ));
+                }
+            }
+        }
+        if let Some(primary) = &self.primary_icon {
+            if !self.icons.iter().any(|icon| icon.name_id == *primary) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Primary icon name ID '{}' was not added", primary),
+                ));
+            }
+            if let Some(first) = self
+                .icons
+                .iter()
+                .min_by_key(|icon| resource_directory_key(&icon.name_id))
+                .filter(|icon| {
+                    resource_directory_key(&icon.name_id) < resource_directory_key(primary)
+                })
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "Icon '{}' would be used as application icon instead of primary icon '{}'",
+                        first.name_id, primary
+                    ),
+                ));
+            }
+        }
+        for (name_id, entries) in &self.accelerators {
+            for entry in entries {
+                if let Err(problem) = entry.check() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!(
+                            "Accelerator table {}: {:?}: {}",
+                            name_id, entry.key, problem
+                        ),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Set the correct path for the toolkit.
+    ///
+    /// For the GNU toolkit this has to be the path where MinGW
+    /// put `windres.exe` and `ar.exe`. This could be something like:
+    /// `"C:\Program Files\mingw-w64\x86_64-5.3.0-win32-seh-rt_v4-rev0\mingw64\bin"`
+    ///
+    /// For MSVC the Windows SDK has to be installed. It comes with the resource compiler
+    /// `rc.exe`. This should be set to the root directory of the Windows SDK, e.g.,
+    /// `"C:\Program Files (x86)\Windows Kits\10"`
+    /// or, if multiple 10 versions are installed,
+    /// set it directly to the corret bin directory
+    /// `"C:\Program Files (x86)\Windows Kits\10\bin\10.0.14393.0\x64"`
+    ///
+    /// If it is left unset, it will look up a path in the registry,
+    /// i.e. `HKLM\SOFTWARE\Microsoft\Windows Kits\Installed Roots`
+    pub fn set_toolkit_path<'a>(&mut self, path: &'a str) -> &mut Self {
+        self.toolkit_path = PathBuf::from(path);
+        self
+    }
+
+    /// Use an already detected Windows SDK as the MSVC toolkit.
+    ///
+    /// This works like [`set_toolkit_path()`] with the `bin` directory of the SDK, but
+    /// the include directories found by [`Sdk::detect()`] are reused.
+    ///
+    /// [`set_toolkit_path()`]: #method.set_toolkit_path
+    /// [`Sdk::detect()`]: struct.Sdk.html#method.detect
+    pub fn set_sdk(&mut self, sdk: &Sdk) -> &mut Self {
+        self.toolkit_path = sdk.bin.clone();
+        self.sdk = Some(sdk.clone());
+        self
+    }
+
+    /// Use a specific version of the Windows SDK, e.g., `10.0.19041.0`.
+    ///
+    /// By default the newest SDK listed in the registry is used. This function
+    /// pins the version for reproducible builds and fails if it is not installed,
+    /// see [`Sdk::detect_version()`] and [`set_sdk()`].
+    ///
+    /// [`Sdk::detect_version()`]: struct.Sdk.html#method.detect_version
+    /// [`set_sdk()`]: #method.set_sdk
+    pub fn set_sdk_version(&mut self, version: &str) -> io::Result<&mut Self> {
+        let sdk = Sdk::detect_version(version)?;
+        self.log_messages.push((
+            LogLevel::Verbose,
+            format!("Using Windows SDK {}: {}", version, sdk.bin.display()),
+        ));
+        Ok(self.set_sdk(&sdk))
+    }
+
+    /// Add a candidate path for the MSVC toolkit.
+    ///
+    /// The candidates are tried in the order they were added, the first one that
+    /// contains `rc.exe` (directly or in `bin\x64` resp. `bin\x86`) is used. The path
+    /// set with [`set_toolkit_path()`], or found in the registry, is tried last.
+    /// When none of them contains `rc.exe`, [`compile()`] fails and lists the paths
+    /// it tried. Use this on CI fleets where the SDK is installed in different
+    /// places per image.
+    ///
+    /// This setting has no effect for the GNU toolkit.
+    ///
+    /// [`set_toolkit_path()`]: #method.set_toolkit_path
+    /// [`compile()`]: #method.compile
+    pub fn add_toolkit_path<P: AsRef<Path>>(&mut self, path: P) -> &mut Self {
+        self.toolkit_candidates.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Set the user interface language of the file
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// extern crate winapi;
+    /// extern crate winres;
+    /// # use std::io;
+    /// fn main() {
+    ///   if cfg!(target_os = "windows") {
+    ///     let mut res = winres::WindowsResource::new();
+    /// #   res.set_output_directory(".");
+    ///     res.set_language(winapi::um::winnt::MAKELANGID(
     ///         winapi::um::winnt::LANG_ENGLISH,
     ///         winapi::um::winnt::SUBLANG_ENGLISH_US
     ///     ));
@@ -324,11 +1685,98 @@ impl WindowsResource {
     /// | Breton              | `0x007e` |
     /// | Scottish Gaelic     | `0x0091` |
     /// | Romansch            | `0x0017` |
+    ///
+    /// All of them can also be set with [`set_language_str()`] and a tag like `en-GB`.
+    ///
+    /// The string properties are written for this language only; use
+    /// [`add_language_strings()`] to add translated strings for other languages.
+    ///
+    /// [`set_language_str()`]: #method.set_language_str
+    /// [`add_language_strings()`]: #method.add_language_strings
     pub fn set_language(&mut self, language: u16) -> &mut Self {
         self.language = language;
         self
     }
 
+    /// Set the user interface language of the file from a language tag like `en-US`.
+    ///
+    /// This is the same as [`set_language()`] with the LANGID of the tag, e.g.,
+    /// `0x0409` for `en-US`. The tag is case insensitive and `_` can be used instead
+    /// of `-`. A tag without a region, like `de`, is the language with the neutral
+    /// sublanguage, e.g., `0x0007`; `und` is the neutral language `0x0000`. Only the
+    /// common languages are known, an `InvalidInput` error is returned for other
+    /// tags, use [`set_language()`] for them.
+    ///
+    /// [`set_language()`]: #method.set_language
+    pub fn set_language_str(&mut self, tag: &str) -> io::Result<&mut Self> {
+        let language = language_id(tag).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unknown language tag {:?}", tag),
+            )
+        })?;
+        Ok(self.set_language(language))
+    }
+
+    /// The language set with [`set_language()`], `0` (neutral) by default
+    ///
+    /// [`set_language()`]: #method.set_language
+    pub fn language(&self) -> u16 {
+        self.language
+    }
+
+    /// Set the codepage of the default string block, `0x04b0` (Unicode) by default
+    ///
+    /// The codepage is part of the `StringFileInfo` block key, e.g., `040904e4`,
+    /// and of the `Translation` value in the `VarFileInfo`. Some tools expect
+    /// `0x04e4` (Windows-1252) here instead of Unicode.
+    pub fn set_codepage(&mut self, codepage: u16) -> &mut Self {
+        self.codepage = codepage;
+        self
+    }
+
+    /// Add translated string properties for another language
+    ///
+    /// Every call adds a `StringFileInfo` block keyed with the language and the
+    /// codepage, e.g., `040704e4` for German with the Windows-1252 codepage `0x04e4`,
+    /// and the pair to the `Translation` list of the `VarFileInfo`. The block contains
+    /// the properties set with [`set()`], overridden by `properties`. The default
+    /// block for [`set_language()`] and [`set_codepage()`] is always written first,
+    /// unless it is replaced by a block with the same key.
+    /// Adding the same language and codepage again replaces the properties.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut res = winres::WindowsResource::new();
+    /// res.set_language(0x0409)
+    ///     .set("FileDescription", "Spreadsheet")
+    ///     .add_language_strings(0x0407, 0x04b0, &[("FileDescription", "Tabellenkalkulation")]);
+    /// ```
+    ///
+    /// [`set()`]: #method.set
+    /// [`set_language()`]: #method.set_language
+    /// [`set_codepage()`]: #method.set_codepage
+    pub fn add_language_strings(
+        &mut self,
+        language: u16,
+        codepage: u16,
+        properties: &[(&str, &str)],
+    ) -> &mut Self {
+        let properties = properties
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        self.language_strings
+            .retain(|l| l.language != language || l.codepage != codepage);
+        self.language_strings.push(LanguageStrings {
+            language,
+            codepage,
+            properties,
+        });
+        self
+    }
+
     /// Add an icon with nameID `1`.
     ///
     /// This icon need to be in `ico` format. The filename can be absolute
@@ -342,15 +1790,20 @@ impl WindowsResource {
     /// Add an icon with the specified name ID.
     ///
     /// This icon need to be in `ico` format. The path can be absolute or
-    /// relative to the projects root.
+    /// relative to the projects root. The file is not read here, but [`compile()`]
+    /// returns [`WinresError::InvalidResourceFiles`] if it is missing or not an `.ico` file.
+    ///
+    /// [`compile()`]: #method.compile
+    /// [`validate()`]: #method.validate
+    /// [`WinresError::InvalidResourceFiles`]: enum.WinresError.html#variant.InvalidResourceFiles
     ///
     /// ## Name ID and Icon Loading
     ///
     /// The name ID can be (the string representation of) a 16-bit unsigned
     /// integer, or some other string.
     ///
-    /// You should not add multiple icons with the same name ID. It will result
-    /// in a build failure.
+    /// You should not add multiple icons with the same name ID, [`validate()`]
+    /// and thus [`compile()`] fail in that case.
     ///
     /// When the name ID is an integer, the icon can be loaded at runtime with
     ///
@@ -386,464 +1839,4982 @@ impl WindowsResource {
     ///    .set_icon_with_id("icon3.icon", "3")
     ///    // ...
     /// ```
+    ///
+    /// Use [`set_primary_icon()`] to have [`compile()`] check the choice.
+    ///
+    /// [`set_primary_icon()`]: #method.set_primary_icon
     pub fn set_icon_with_id<'a>(&mut self, path: &'a str, name_id: &'a str) -> &mut Self {
-        self.icons.push(Icon {
+        self.icons.push(Image {
             path: path.into(),
             name_id: name_id.into(),
         });
         self
     }
 
-    /// Set a version info struct property
-    /// Currently we only support numeric values; you have to look them up.
-    pub fn set_version_info(&mut self, field: VersionInfo, value: u64) -> &mut Self {
-        self.version_info.insert(field, value);
+    /// Set the name ID of the application icon.
+    ///
+    /// The icon has to be added with [`set_icon()`] or [`set_icon_with_id()`], it is
+    /// written first to the resource file. Windows uses the icon that comes first in
+    /// the resource directory, i.e. the alphabetically first string name ID, or if
+    /// there is none, the lowest integer name ID. [`validate()`] and thus
+    /// [`compile()`] fail if the name ID was not added, or if another icon would be
+    /// chosen instead.
+    ///
+    /// [`set_icon()`]: #method.set_icon
+    /// [`set_icon_with_id()`]: #method.set_icon_with_id
+    /// [`validate()`]: #method.validate
+    /// [`compile()`]: #method.compile
+    pub fn set_primary_icon(&mut self, name_id: &str) -> &mut Self {
+        self.primary_icon = Some(name_id.into());
         self
     }
 
-    /// Set the embedded manifest file
-    ///
-    /// # Example
+    /// The icons in the order they are written, the primary icon comes first
+    fn ordered_icons(&self) -> Vec<&Image> {
+        let mut icons: Vec<&Image> = self.icons.iter().collect();
+        if let Some(primary) = &self.primary_icon {
+            icons.sort_by_key(|icon| icon.name_id != *primary);
+        }
+        icons
+    }
+
+    /// The paths of the icons in the order they were added
+    pub fn icons(&self) -> impl Iterator<Item = &str> {
+        self.icons.iter().map(|icon| icon.path.as_str())
+    }
+
+    /// Add a bitmap with the specified name ID.
     ///
-    /// The following manifest will brand the exe as requesting administrator privileges.
-    /// Thus, everytime it is executed, a Windows UAC dialog will appear.
+    /// This bitmap needs to be in `bmp` format, which [`compile()`] checks. The path
+    /// can be absolute or relative to the projects root. Like for icons, you should
+    /// not add multiple bitmaps with the same name ID, see [`set_icon_with_id()`].
+    /// At runtime the bitmap can be loaded with `LoadBitmapW` or `LoadImageW`.
     ///
-    /// ```rust
-    /// let mut res = winres::WindowsResource::new();
-    /// res.set_manifest(r#"
-    /// <assembly xmlns="urn:schemas-microsoft-com:asm.v1" manifestVersion="1.0">
-    /// <trustInfo xmlns="urn:schemas-microsoft-com:asm.v3">
-    ///     <security>
-    ///         <requestedPrivileges>
-    ///             <requestedExecutionLevel level="requireAdministrator" uiAccess="false" />
-    ///         </requestedPrivileges>
-    ///     </security>
-    /// </trustInfo>
-    /// </assembly>
-    /// "#);
-    /// ```
-    pub fn set_manifest<'a>(&mut self, manifest: &'a str) -> &mut Self {
-        self.manifest_file = None;
-        self.manifest = Some(manifest.to_string());
+    /// [`compile()`]: #method.compile
+    /// [`set_icon_with_id()`]: #method.set_icon_with_id
+    pub fn add_bitmap(&mut self, name_id: &str, path: &str) -> &mut Self {
+        self.bitmaps.push(Image {
+            path: path.into(),
+            name_id: name_id.into(),
+        });
         self
     }
 
-    /// Some as [`set_manifest()`] but a filename can be provided and
-    /// file is included by the resource compieler itself.
-    /// This method works the same way as [`set_icon()`]
+    /// Add a cursor with the specified name ID.
     ///
-    /// [`set_manifest()`]: #method.set_manifest
-    /// [`set_icon()`]: #method.set_icon
-    pub fn set_manifest_file<'a>(&mut self, file: &'a str) -> &mut Self {
-        self.manifest_file = Some(file.to_string());
-        self.manifest = None;
+    /// This cursor needs to be in `cur` format, which [`compile()`] checks. The path
+    /// can be absolute or relative to the projects root. Like for icons, you should
+    /// not add multiple cursors with the same name ID, see [`set_icon_with_id()`].
+    /// At runtime the cursor can be loaded with `LoadCursorW` or `LoadImageW`.
+    ///
+    /// [`compile()`]: #method.compile
+    /// [`set_icon_with_id()`]: #method.set_icon_with_id
+    pub fn add_cursor(&mut self, name_id: &str, path: &str) -> &mut Self {
+        self.cursors.push(Image {
+            path: path.into(),
+            name_id: name_id.into(),
+        });
         self
     }
 
-    /// Set the path to the windres executable.
-    pub fn set_windres_path(&mut self, path: &str) -> &mut Self {
-        self.windres_path = path.to_string();
+    /// Add an accelerator table with the specified name ID.
+    ///
+    /// The entries are written as a `name_id ACCELERATORS` block, which can be loaded
+    /// at runtime with `LoadAcceleratorsW`. Adding a table with the same name ID
+    /// again replaces it. [`compile()`] checks the modifiers of the entries.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use winres::{AcceleratorEntry, AcceleratorKey};
+    ///
+    /// let mut res = winres::WindowsResource::new();
+    /// res.add_accelerator(
+    ///     "shortcuts",
+    ///     &[
+    ///         // Ctrl+S
+    ///         AcceleratorEntry {
+    ///             key: AcceleratorKey::VirtKey(0x53),
+    ///             command: 100,
+    ///             control: true,
+    ///             shift: false,
+    ///             alt: false,
+    ///         },
+    ///         // F1
+    ///         AcceleratorEntry {
+    ///             key: AcceleratorKey::VirtKey(0x70),
+    ///             command: 101,
+    ///             control: false,
+    ///             shift: false,
+    ///             alt: false,
+    ///         },
+    ///     ],
+    /// );
+    /// ```
+    ///
+    /// [`compile()`]: #method.compile
+    pub fn add_accelerator(&mut self, name_id: &str, entries: &[AcceleratorEntry]) -> &mut Self {
+        self.accelerators.retain(|(id, _)| id != name_id);
+        self.accelerators
+            .push((name_id.to_string(), entries.to_vec()));
         self
     }
 
-    /// Set the path to the ar executable.
-    pub fn set_ar_path(&mut self, path: &str) -> &mut Self {
-        self.ar_path = path.to_string();
+    /// Add a string to the string table.
+    ///
+    /// All strings are written to one `STRINGTABLE` block in the language set with
+    /// [`set_language()`], and can be loaded at runtime with `LoadStringW`. Every ID
+    /// can only be used once, [`compile()`] fails for duplicates.
+    ///
+    /// [`set_language()`]: #method.set_language
+    /// [`compile()`]: #method.compile
+    pub fn add_string(&mut self, id: u16, text: &str) -> &mut Self {
+        self.strings.push((id, text.to_string()));
         self
     }
 
-    /// Set the path to the ar executable.
-    pub fn add_toolkit_include(&mut self, add: bool) -> &mut Self {
-        self.add_toolkit_include = add;
-        self
+    /// Add an icon with nameID `1` from the content of an `.ico` file.
+    ///
+    /// Equivalent to `set_icon_from_bytes_with_id(data, "1")`.
+    pub fn set_icon_from_bytes(&mut self, data: &'static [u8]) -> io::Result<&mut Self> {
+        self.set_icon_from_bytes_with_id(data, "1")
     }
 
-    /// Write a resource file with the set values
-    pub fn write_resource_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
-        let mut f = fs::File::create(path)?;
-
-        // use UTF8 as an encoding
-        // this makes it easier since in rust all string are UTF8
-        writeln!(f, "#pragma code_page(65001)")?;
-        writeln!(f, "1 VERSIONINFO")?;
-        for (k, v) in self.version_info.iter() {
-            match *k {
-                VersionInfo::FILEVERSION | VersionInfo::PRODUCTVERSION => writeln!(
-                    f,
-                    "{:?} {}, {}, {}, {}",
-                    k,
-                    (*v >> 48) as u16,
-                    (*v >> 32) as u16,
-                    (*v >> 16) as u16,
-                    *v as u16
-                )?,
-                _ => writeln!(f, "{:?} {:#x}", k, v)?,
-            };
+    /// Add an icon with the specified name ID from the content of an `.ico` file.
+    ///
+    /// This is intended for icons embedded in the build script with `include_bytes!`.
+    /// The resource compiler needs a file, so the data is copied to the output
+    /// directory. The file name is derived from a hash of the content, which keeps
+    /// builds reproducible. See [`set_icon_with_id()`] for details on name IDs.
+    ///
+    /// The file is written when this function is called, so call
+    /// [`set_output_directory()`] before it. A later change of the output directory
+    /// leaves the file in the previous one.
+    ///
+    /// ```rust,no_run
+    /// # extern crate winres;
+    /// # fn main() -> std::io::Result<()> {
+    /// let mut res = winres::WindowsResource::new();
+    /// res.set_icon_from_bytes(include_bytes!("test.ico"))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`set_icon_with_id()`]: #method.set_icon_with_id
+    /// [`set_output_directory()`]: #method.set_output_directory
+    pub fn set_icon_from_bytes_with_id(
+        &mut self,
+        data: &'static [u8],
+        name_id: &str,
+    ) -> io::Result<&mut Self> {
+        let path = PathBuf::from(&self.output_directory)
+            .join(format!("icon-{:016x}.ico", content_hash(data)));
+        if !path.exists() {
+            fs::write(&path, data)?;
         }
-        writeln!(f, "{{\nBLOCK \"StringFileInfo\"")?;
-        writeln!(f, "{{\nBLOCK \"{:04x}04b0\"\n{{", self.language)?;
-        for (k, v) in self.properties.iter() {
-            if !v.is_empty() {
-                writeln!(
-                    f,
-                    "VALUE \"{}\", \"{}\"",
-                    escape_string(k),
-                    escape_string(v)
-                )?;
+        Ok(self.set_icon_with_id(&path.to_string_lossy(), name_id))
+    }
+
+    /// Add an icon assembled from separate PNG or BMP images.
+    ///
+    /// Each image is given with its size in pixels, e.g.,
+    /// `&[(16, "16.png"), (32, "32.png"), (256, "256.png")]`, and must be a square
+    /// of exactly that size. The paths can be absolute or relative to the projects
+    /// root. The images are packed into a single `.ico` file in the output
+    /// directory, which is then added like [`set_icon_with_id()`]. PNG images are
+    /// stored as they are, which Windows supports for all sizes and requires for
+    /// 256 pixels in practice, BMP images have to be uncompressed.
+    ///
+    /// Only available with the `conversion` feature.
+    ///
+    /// [`set_icon_with_id()`]: #method.set_icon_with_id
+    #[cfg(feature = "conversion")]
+    pub fn set_icon_from_images(
+        &mut self,
+        images: &[(u32, &str)],
+        name_id: &str,
+    ) -> io::Result<&mut Self> {
+        let mut entries = Vec::new();
+        for &(size, path) in images {
+            let file = self.resolve_path(path);
+            let data = fs::read(&file)?;
+            self.input_files.push(file);
+            let image = icon_image(data)
+                .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)))?;
+            if image.width != size || image.height != size {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "{}: image is {}x{} pixels, expected {}x{}",
+                        path, image.width, image.height, size, size
+                    ),
+                ));
             }
+            entries.push(image);
         }
-        writeln!(f, "}}\n}}")?;
 
-        writeln!(f, "BLOCK \"VarFileInfo\" {{")?;
-        writeln!(f, "VALUE \"Translation\", {:#x}, 0x04b0", self.language)?;
-        writeln!(f, "}}\n}}")?;
-        for icon in &self.icons {
-            writeln!(
-                f,
-                "{} ICON \"{}\"",
-                escape_string(&icon.name_id),
-                escape_string(&icon.path)
-            )?;
-        }
-        if let Some(e) = self.version_info.get(&VersionInfo::FILETYPE) {
-            if let Some(manf) = self.manifest.as_ref() {
-                writeln!(f, "{} 24", e)?;
-                writeln!(f, "{{")?;
-                for line in manf.lines() {
-                    writeln!(f, "\" {} \"", escape_string(line.trim()))?;
+        let ico = self.generated_icon_path(name_id);
+        fs::write(&ico, write_ico(&entries))?;
+        Ok(self.set_icon_with_id(&ico.to_string_lossy(), name_id))
+    }
+
+    /// Add an icon group with the images of several files.
+    ///
+    /// Every `ICON` statement of the resource file adds a `GROUP_ICON` resource
+    /// with the given name ID, which refers to one `ICON` resource per image. This
+    /// function lets you choose which images are in one group: the images of all
+    /// files are combined in a single `.ico` file in the output directory, which
+    /// is then added like [`set_icon_with_id()`]. The files can be `.ico` files,
+    /// whose images are all used, and PNG or BMP images like for
+    /// [`set_icon_from_images()`]. It is an error if two images have the same size
+    /// and color depth.
+    ///
+    /// Only available with the `conversion` feature.
+    ///
+    /// # Example
+    ///
+    /// ```nocheck
+    /// res.add_icon_group("1", &["app-small.ico", "app-256.png"])?
+    ///    .add_icon_group("2", &["installer.ico"])?;
+    /// ```
+    ///
+    /// [`set_icon_with_id()`]: #method.set_icon_with_id
+    /// [`set_icon_from_images()`]: #method.set_icon_from_images
+    #[cfg(feature = "conversion")]
+    pub fn add_icon_group(&mut self, name_id: &str, paths: &[&str]) -> io::Result<&mut Self> {
+        let mut entries: Vec<IcoImage> = Vec::new();
+        for &path in paths {
+            let file = self.resolve_path(path);
+            let data = fs::read(&file)?;
+            self.input_files.push(file);
+            let images = if data.starts_with(&[0, 0, 1, 0]) {
+                read_ico(&data)
+            } else {
+                icon_image(data).map(|image| vec![image])
+            }
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)))?;
+            for image in images {
+                if entries.iter().any(|e| {
+                    (e.width, e.height, e.bit_count) == (image.width, image.height, image.bit_count)
+                }) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!(
+                            "{}: icon group {} already has a {}x{} image with {} bits per pixel",
+                            path, name_id, image.width, image.height, image.bit_count
+                        ),
+                    ));
                 }
-                writeln!(f, "}}")?;
-            } else if let Some(manf) = self.manifest_file.as_ref() {
-                writeln!(f, "{} 24 \"{}\"", e, escape_string(manf))?;
+                entries.push(image);
             }
         }
-        writeln!(f, "{}", self.append_rc_content)?;
-        Ok(())
+
+        let ico = self.generated_icon_path(name_id);
+        fs::write(&ico, write_ico(&entries))?;
+        Ok(self.set_icon_with_id(&ico.to_string_lossy(), name_id))
     }
 
-    /// Set a path to an already existing resource file.
+    /// Add an icon with only some of the images of an `.ico` file.
     ///
-    /// We will neither modify this file nor parse its contents. This function
-    /// simply replaces the internaly generated resource file that is passed to
-    /// the compiler. You can use this function to write a resource file yourself.
-    pub fn set_resource_file<'a>(&mut self, path: &'a str) -> &mut Self {
-        self.rc_file = Some(path.to_string());
+    /// Icons often contain many sizes, which all end up in the binary. This function
+    /// keeps only the square images with the given `sizes` in pixels, e.g.,
+    /// `&[16, 32]`, writes them to a new `.ico` file in the output directory and adds
+    /// it like [`set_icon_with_id()`]. All color depths of a kept size are kept.
+    /// It is an error if `path` does not contain one of the sizes.
+    ///
+    /// Only available with the `conversion` feature.
+    ///
+    /// [`set_icon_with_id()`]: #method.set_icon_with_id
+    #[cfg(feature = "conversion")]
+    pub fn set_icon_filtered(
+        &mut self,
+        path: &str,
+        sizes: &[u32],
+        name_id: &str,
+    ) -> io::Result<&mut Self> {
+        let file = self.resolve_path(path);
+        let data = fs::read(&file)?;
+        self.input_files.push(file);
+        let images =
+            read_ico(&data).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)))?;
+        for size in sizes {
+            if !images.iter().any(|i| i.width == *size && i.height == *size) {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("{}: icon has no {}x{} image", path, size, size),
+                ));
+            }
+        }
+        let images: Vec<IcoImage> = images
+            .into_iter()
+            .filter(|i| i.width == i.height && sizes.contains(&i.width))
+            .collect();
+
+        let ico = self.generated_icon_path(name_id);
+        fs::write(&ico, write_ico(&images))?;
+        Ok(self.set_icon_with_id(&ico.to_string_lossy(), name_id))
+    }
+
+    /// The path of an icon file assembled by winres
+    #[cfg(feature = "conversion")]
+    fn generated_icon_path(&self, name_id: &str) -> PathBuf {
+        let name: String = name_id
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        PathBuf::from(&self.output_directory).join(format!("icon_{}.ico", name))
+    }
+
+    /// Embed the license of the crate.
+    ///
+    /// The SPDX expression from `package.license` is set as the `"License"` string
+    /// property of the version info. If `package.license-file` is set, the file is
+    /// embedded as an `RCDATA` resource with the name ID [`LICENSE_RESOURCE`], which
+    /// can be loaded with `FindResourceW(h_instance, "LICENSE", RT_RCDATA)`.
+    /// If neither is set in `Cargo.toml`, nothing is embedded.
+    ///
+    /// [`LICENSE_RESOURCE`]: constant.LICENSE_RESOURCE.html
+    pub fn embed_license(&mut self) -> &mut Self {
+        self.embed_license_from(
+            &env::var("CARGO_PKG_LICENSE").unwrap_or_default(),
+            &env::var("CARGO_PKG_LICENSE_FILE").unwrap_or_default(),
+            Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap_or_default()),
+        )
+    }
+
+    /// [`embed_license()`] with the values of `package.license` and
+    /// `package.license-file`, empty if they are not set
+    ///
+    /// [`embed_license()`]: #method.embed_license
+    fn embed_license_from(&mut self, license: &str, file: &str, root: &Path) -> &mut Self {
+        if !license.is_empty() {
+            self.set("License", license);
+        }
+        if !file.is_empty() {
+            let path = root.join(file);
+            self.add_resource(
+                ResourceType::RcData,
+                LICENSE_RESOURCE,
+                &path.to_string_lossy(),
+            );
+        }
         self
     }
 
-    /// Append an additional snippet to the generated rc file.
+    /// Embed binary data as an `RCDATA` resource with the given name ID.
     ///
-    /// # Example
+    /// The data is written to a file in the output directory, which is referenced
+    /// from the resource file, so any bytes can be embedded without escaping. Like
+    /// [`set_icon_from_bytes_with_id()`] the file name is derived from a hash of the
+    /// content. Adding data with the same name ID again replaces it. At runtime the
+    /// data can be loaded with `FindResourceW(h_instance, name_id, RT_RCDATA)`,
+    /// `LoadResource` and `SizeofResource`.
     ///
-    /// Define a menu resource:
+    /// The file is written when this function is called, so call
+    /// [`set_output_directory()`] before it, as for icons from bytes.
     ///
-    /// ```rust
+    /// ```rust,no_run
     /// # extern crate winres;
-    /// # if cfg!(target_os = "windows") {
-    ///     let mut res = winres::WindowsResource::new();
-    ///     res.append_rc_content(r##"sample MENU
-    /// {
-    ///     MENUITEM "&Soup", 100
-    ///     MENUITEM "S&alad", 101
-    ///     POPUP "&Entree"
-    ///     {
-    ///          MENUITEM "&Fish", 200
-    ///          MENUITEM "&Chicken", 201, CHECKED
-    ///          POPUP "&Beef"
-    ///          {
-    ///               MENUITEM "&Steak", 301
-    ///               MENUITEM "&Prime Rib", 302
-    ///          }
-    ///     }
-    ///     MENUITEM "&Dessert", 103
-    /// }"##);
-    /// #    res.compile()?;
+    /// # fn main() -> std::io::Result<()> {
+    /// let mut res = winres::WindowsResource::new();
+    /// res.add_rcdata("DEFAULT_CONFIG", b"[settings]\nverbose = false\n")?;
+    /// # Ok(())
     /// # }
-    /// # Ok::<_, std::io::Error>(())
     /// ```
-    pub fn append_rc_content<'a>(&mut self, content: &'a str) -> &mut Self {
-        if !(self.append_rc_content.ends_with('\n') || self.append_rc_content.is_empty()) {
-            self.append_rc_content.push('\n');
+    ///
+    /// [`set_icon_from_bytes_with_id()`]: #method.set_icon_from_bytes_with_id
+    /// [`set_output_directory()`]: #method.set_output_directory
+    pub fn add_rcdata(&mut self, name_id: &str, data: &[u8]) -> io::Result<&mut Self> {
+        let path = PathBuf::from(&self.output_directory)
+            .join(format!("rcdata-{:016x}.bin", content_hash(data)));
+        if !path.exists() {
+            fs::write(&path, data)?;
         }
-        self.append_rc_content.push_str(content);
+        self.resources.retain(|r| r.name_id != name_id);
+        self.resources.push(CustomResource {
+            name_id: name_id.to_string(),
+            type_id: "RCDATA".to_string(),
+            path: path.to_string_lossy().into_owned(),
+        });
+        Ok(self)
+    }
+
+    /// Include a file as a resource of the given type and name ID.
+    ///
+    /// This writes `name_id type "path"` to the resource file, the path can be
+    /// absolute or relative to the projects root. Use it for resource types without
+    /// a dedicated function, e.g., a type library with [`ResourceType::TypeLib`].
+    /// Adding a resource with the same type and name ID again replaces it. At
+    /// runtime the resource can be loaded with `FindResourceW(h_instance, name_id,
+    /// type)` and `LoadResource`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut res = winres::WindowsResource::new();
+    /// res.add_resource(winres::ResourceType::TypeLib, "1", "target/app.tlb")
+    ///     .add_resource(winres::ResourceType::Custom(256), "DATA", "assets/data.bin");
+    /// ```
+    ///
+    /// [`ResourceType::TypeLib`]: enum.ResourceType.html#variant.TypeLib
+    pub fn add_resource(
+        &mut self,
+        resource_type: ResourceType,
+        name_id: &str,
+        path: &str,
+    ) -> &mut Self {
+        let type_id = resource_type.rc_type();
+        self.resources
+            .retain(|r| r.name_id != name_id || r.type_id != type_id);
+        self.resources.push(CustomResource {
+            name_id: name_id.to_string(),
+            type_id,
+            path: path.to_string(),
+        });
         self
     }
 
-    /// Override the output directoy.
+    /// Include an HTML page, script or style sheet as an `RT_HTML` resource.
     ///
-    /// As a default, we use `%OUT_DIR%` set by cargo, but it may be necessary to override the
-    /// the setting.
-    pub fn set_output_directory<'a>(&mut self, path: &'a str) -> &mut Self {
-        self.output_directory = path.to_string();
+    /// This is the same as [`add_resource()`] with [`ResourceType::Html`]. Embedded
+    /// browser controls load the page with a `res://<binary>/name_id` URL, or it can
+    /// be read with `FindResourceW(h_instance, name_id, RT_HTML)`. Like for icons,
+    /// [`compile()`] returns [`WinresError::InvalidResourceFiles`] if the file is
+    /// missing.
+    ///
+    /// [`add_resource()`]: #method.add_resource
+    /// [`ResourceType::Html`]: enum.ResourceType.html#variant.Html
+    /// [`compile()`]: #method.compile
+    /// [`WinresError::InvalidResourceFiles`]: enum.WinresError.html#variant.InvalidResourceFiles
+    pub fn add_html(&mut self, name_id: &str, path: &str) -> &mut Self {
+        self.add_resource(ResourceType::Html, name_id, path)
+    }
+
+    /// Embed the AppUserModelID of the application.
+    ///
+    /// The AppUserModelID decides how windows are grouped in the taskbar and which
+    /// application notifications belong to. Windows does not read it from the
+    /// resources, it has to be set at runtime with
+    /// `SetCurrentProcessExplicitAppUserModelID` before any window is created.
+    /// Embedding it keeps the ID in one place with the other metadata of the
+    /// application, instead of hardcoding it in Rust.
+    ///
+    /// The ID is stored as a NUL terminated UTF-16 string in an `RCDATA` resource
+    /// with the name ID [`APP_USER_MODEL_ID_RESOURCE`]. At runtime the pointer
+    /// returned by `LockResource(LoadResource(h_instance, FindResourceW(h_instance,
+    /// "APP_USER_MODEL_ID", RT_RCDATA)))` can be passed to
+    /// `SetCurrentProcessExplicitAppUserModelID` directly.
+    ///
+    /// [`APP_USER_MODEL_ID_RESOURCE`]: constant.APP_USER_MODEL_ID_RESOURCE.html
+    pub fn set_app_user_model_id(&mut self, id: &str) -> &mut Self {
+        self.app_user_model_id = Some(id.to_string());
         self
     }
 
-    fn compile_with_toolkit_gnu<'a>(&self, input: &'a str, output_dir: &'a str) -> io::Result<()> {
-        let output = PathBuf::from(output_dir).join("resource.o");
-        let input = PathBuf::from(input);
-        let status = process::Command::new(&self.windres_path)
-            .current_dir(&self.toolkit_path)
-            .arg(format!("-I{}", env::var("CARGO_MANIFEST_DIR").unwrap()))
-            .arg(format!("{}", input.display()))
-            .arg(format!("{}", output.display()))
-            .status()?;
-        if !status.success() {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                "Could not compile resource file",
-            ));
+    /// Read the properties from `package.metadata.winres` of another `Cargo.toml`
+    ///
+    /// [`new()`] reads them from the `Cargo.toml` of the crate. Use this function when
+    /// the metadata lives elsewhere, e.g., in a shared file of a workspace. The path
+    /// can be absolute or relative to the projects root. The properties override the
+    /// ones already set. Unlike for [`new()`], a missing file or invalid TOML is an
+    /// error. When the metadata has invalid values, nothing is changed.
+    ///
+    /// There is no separate setting for the path of `Cargo.toml`: [`new()`] already
+    /// reads the crate's own file, so this function takes its place for metadata that
+    /// lives in another file.
+    ///
+    /// [`new()`]: #method.new
+    pub fn load_from_toml(&mut self, path: &str) -> io::Result<&mut Self> {
+        let path = self.resolve_path(path);
+        let content = fs::read_to_string(&path).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("Could not read {}: {}", path.display(), e),
+            )
+        })?;
+        let ml = content.parse::<toml::Value>().map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Could not parse {}: {}", path.display(), e),
+            )
+        })?;
+        self.input_files.push(path.clone());
+        let mut properties = HashMap::new();
+        let mut version_info = HashMap::new();
+        let mut log = Vec::new();
+        let mut errors = Vec::new();
+        read_winres_metadata(
+            &ml,
+            &mut properties,
+            &mut version_info,
+            &mut log,
+            &mut errors,
+        );
+        for (level, message) in &log {
+            self.log(*level, message);
         }
-
-        let libname = PathBuf::from(output_dir).join("libresource.a");
-        let status = process::Command::new(&self.ar_path)
-            .current_dir(&self.toolkit_path)
-            .arg("rsc")
-            .arg(format!("{}", libname.display()))
-            .arg(format!("{}", output.display()))
-            .status()?;
-        if !status.success() {
+        if !errors.is_empty() {
             return Err(io::Error::new(
-                io::ErrorKind::Other,
-                "Could not create static library for resource file",
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Invalid metadata in {}: {}",
+                    path.display(),
+                    errors.join(", ")
+                ),
             ));
         }
+        self.properties.extend(properties);
+        self.version_info.extend(version_info);
+        Ok(self)
+    }
 
-        println!("cargo:rustc-link-search=native={}", output_dir);
-        println!("cargo:rustc-link-lib=static=resource");
-
-        Ok(())
+    /// Set a version info struct property
+    /// Currently we only support numeric values; you have to look them up.
+    pub fn set_version_info(&mut self, field: VersionInfo, value: u64) -> &mut Self {
+        self.version_info.insert(field, value);
+        self
     }
 
-    /// Run the resource compiler
+    /// Set the file date of the `VERSIONINFO` resource
     ///
-    /// This function generates a resource file from the settings or
-    /// uses an existing resource file and passes it to the resource compiler
-    /// of your toolkit.
+    /// The date is written to the `dwFileDateMS` (high 32 bits) and `dwFileDateLS`
+    /// (low 32 bits) fields of the fixed version info, which are zero by default.
+    /// Resource scripts have no statement for them, so the fields are patched in the
+    /// compiled resource after the resource compiler ran. This has no effect without a
+    /// `VERSIONINFO` resource, e.g., when it is disabled with
+    /// [`set_version_info_enabled()`].
     ///
-    /// Further more we will print the correct statements for
-    /// `cargo:rustc-link-lib=` and `cargo:rustc-link-search` on the console,
-    /// so that the cargo build script can link the compiled resource file.
-    pub fn compile(&self) -> io::Result<()> {
-        let output = PathBuf::from(&self.output_directory);
-        let rc = output.join("resource.rc");
-        if self.rc_file.is_none() {
-            self.write_resource_file(&rc)?;
-        }
-        let rc = if let Some(s) = self.rc_file.as_ref() {
-            s.clone()
-        } else {
-            rc.to_str().unwrap().to_string()
-        };
-
-        let target_env = std::env::var("CARGO_CFG_TARGET_ENV").unwrap();
-        match target_env.as_str() {
-            "gnu" => self.compile_with_toolkit_gnu(rc.as_str(), &self.output_directory),
-            "msvc" => self.compile_with_toolkit_msvc(rc.as_str(), &self.output_directory),
-            _ => Err(io::Error::new(
-                io::ErrorKind::Other,
-                "Can only compile resource file when target_env is \"gnu\" or \"msvc\"",
-            )),
-        }
+    /// [`set_version_info_enabled()`]: #method.set_version_info_enabled
+    pub fn set_file_date(&mut self, date: u64) -> &mut Self {
+        self.file_date = Some(date);
+        self
     }
 
-    fn compile_with_toolkit_msvc<'a>(&self, input: &'a str, output_dir: &'a str) -> io::Result<()> {
-        let rc_exe = PathBuf::from(&self.toolkit_path).join("rc.exe");
-        let rc_exe = if !rc_exe.exists() {
-            if cfg!(target_arch = "x86_64") {
-                PathBuf::from(&self.toolkit_path).join(r"bin\x64\rc.exe")
-            } else {
-                PathBuf::from(&self.toolkit_path).join(r"bin\x86\rc.exe")
-            }
-        } else {
-            rc_exe
-        };
-        println!("Selected RC path: '{}'", rc_exe.display());
-        let output = PathBuf::from(output_dir).join("resource.lib");
-        let input = PathBuf::from(input);
-        let mut command = process::Command::new(&rc_exe);
-        let command = command.arg(format!("/I{}", env::var("CARGO_MANIFEST_DIR").unwrap()));
-
-        if self.add_toolkit_include {
-            let root = win_sdk_inlcude_root(&rc_exe);
-            println!("Adding toolkit include: {}", root.display());
-            command.arg(format!("/I{}", root.join("um").display()));
-            command.arg(format!("/I{}", root.join("shared").display()));
-        }
-
-        let status = command
-            .arg(format!("/fo{}", output.display()))
-            .arg(format!("{}", input.display()))
-            .output()?;
+    /// Remove a version info struct property, so it is not written to the resource file
+    ///
+    /// All properties are set to defaults by [`new()`]; use this function for
+    /// resources that only need some of them, e.g., only `FILEVERSION` and `PRODUCTVERSION`.
+    ///
+    /// [`new()`]: #method.new
+    pub fn remove_version_info(&mut self, field: VersionInfo) -> &mut Self {
+        self.version_info.remove(&field);
+        self
+    }
 
-        println!(
-            "RC Output:\n{}\n------",
-            String::from_utf8_lossy(&status.stdout)
-        );
-        println!(
-            "RC Error:\n{}\n------",
-            String::from_utf8_lossy(&status.stderr)
-        );
-        if !status.status.success() {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                "Could not compile resource file",
-            ));
-        }
+    /// Enable or disable the `VERSIONINFO` resource.
+    ///
+    /// It is enabled by default. When disabled, the `VERSIONINFO` block with its
+    /// `StringFileInfo` and `VarFileInfo` is not written, only icons, the manifest
+    /// and the other resources are. Use this when the version info is added with
+    /// [`append_rc_content()`] or a hand-written script, which would conflict.
+    ///
+    /// [`append_rc_content()`]: #method.append_rc_content
+    pub fn set_version_info_enabled(&mut self, enabled: bool) -> &mut Self {
+        self.emit_version_info = enabled;
+        self
+    }
 
-        println!("cargo:rustc-link-search=native={}", output_dir);
-        println!("cargo:rustc-link-lib=dylib=resource");
-        Ok(())
+    /// The value of a version info struct property, `None` if it was removed
+    ///
+    /// E.g., `FILEVERSION` is the package version from `Cargo.toml` unless it was
+    /// changed, see [`new()`].
+    ///
+    /// [`new()`]: #method.new
+    pub fn version_info(&self, field: &VersionInfo) -> Option<u64> {
+        self.version_info.get(field).cloned()
     }
-}
 
-/// Find a Windows SDK
-fn get_sdk() -> io::Result<Vec<PathBuf>> {
-    // use the reg command, so we don't need a winapi dependency
-    let output = process::Command::new("reg")
-        .arg("query")
-        .arg(r"HKLM\SOFTWARE\Microsoft\Windows Kits\Installed Roots")
-        .arg("/reg:32")
-        .output()?;
+    /// Set the embedded manifest file
+    ///
+    /// # Example
+    ///
+    /// The following manifest will brand the exe as requesting administrator privileges.
+    /// Thus, everytime it is executed, a Windows UAC dialog will appear.
+    ///
+    /// ```rust
+    /// let mut res = winres::WindowsResource::new();
+    /// res.set_manifest(r#"
+    /// <assembly xmlns="urn:schemas-microsoft-com:asm.v1" manifestVersion="1.0">
+    /// <trustInfo xmlns="urn:schemas-microsoft-com:asm.v3">
+    ///     <security>
+    ///         <requestedPrivileges>
+    ///             <requestedExecutionLevel level="requireAdministrator" uiAccess="false" />
+    ///         </requestedPrivileges>
+    ///     </security>
+    /// </trustInfo>
+    /// </assembly>
+    /// "#);
+    /// ```
+    ///
+    /// The same is done by [`set_requested_execution_level()`].
+    ///
+    /// [`set_requested_execution_level()`]: #method.set_requested_execution_level
+    pub fn set_manifest<'a>(&mut self, manifest: &'a str) -> &mut Self {
+        self.set_manifest_source(None, ManifestSource::Inline(manifest.to_string()));
+        self
+    }
 
-    if !output.status.success() {
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
-            format!(
-                "Querying the registry failed with error message:\n{}",
-                String::from_utf8(output.stderr)
-                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?
-            ),
-        ));
+    /// Some as [`set_manifest()`] but a filename can be provided and
+    /// file is included by the resource compieler itself.
+    /// This method works the same way as [`set_icon()`]
+    ///
+    /// [`set_manifest()`]: #method.set_manifest
+    /// [`set_icon()`]: #method.set_icon
+    pub fn set_manifest_file<'a>(&mut self, file: &'a str) -> &mut Self {
+        self.set_manifest_source(None, ManifestSource::File(file.to_string()));
+        self
     }
 
-    let lines = String::from_utf8(output.stdout)
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
-    let mut kits: Vec<PathBuf> = Vec::new();
-    let mut lines: Vec<&str> = lines.lines().collect();
-    lines.reverse();
-    for line in lines {
-        if line.trim().starts_with("KitsRoot") {
-            let kit: String = line
-                .chars()
-                .skip(line.find("REG_SZ").unwrap() + 6)
-                .skip_while(|c| c.is_whitespace())
-                .collect();
+    /// Read a manifest file and embed its content like [`set_manifest()`].
+    ///
+    /// Unlike [`set_manifest_file()`] the resource compiler never sees the path, which
+    /// avoids problems with paths it cannot handle. The path is resolved with
+    /// [`resolve_path()`] and the file is read immediately, so an error is returned if
+    /// it cannot be read. Manifest settings like [`set_dpi_awareness()`] are merged
+    /// into the content.
+    ///
+    /// [`set_manifest()`]: #method.set_manifest
+    /// [`set_manifest_file()`]: #method.set_manifest_file
+    /// [`resolve_path()`]: #method.resolve_path
+    /// [`set_dpi_awareness()`]: #method.set_dpi_awareness
+    pub fn set_manifest_file_inline(&mut self, file: &str) -> io::Result<&mut Self> {
+        let path = self.resolve_path(file);
+        let manifest = fs::read_to_string(&path).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("Could not read manifest {}: {}", path.display(), e),
+            )
+        })?;
+        self.input_files.push(path);
+        Ok(self.set_manifest(&manifest))
+    }
 
-            let p = PathBuf::from(&kit);
-            let rc = if cfg!(target_arch = "x86_64") {
-                p.join(r"bin\x64\rc.exe")
-            } else {
-                p.join(r"bin\x86\rc.exe")
-            };
+    /// Use the segment heap instead of the NT heap (Windows 10 2004 and later).
+    ///
+    /// This adds `<heapType>SegmentHeap</heapType>` to the `<windowsSettings>` of the
+    /// application manifest. Like all manifest settings it is merged into a manifest
+    /// set with [`set_manifest()`], or a minimal manifest is generated if none was set.
+    /// Settings cannot be merged into a file set with [`set_manifest_file()`];
+    /// writing the resource file fails in that case.
+    ///
+    /// [`set_manifest()`]: #method.set_manifest
+    /// [`set_manifest_file()`]: #method.set_manifest_file
+    pub fn set_segment_heap(&mut self, enable: bool) -> &mut Self {
+        let value = if enable { Some("SegmentHeap") } else { None };
+        self.set_windows_setting(
+            "heapType",
+            "http://schemas.microsoft.com/SMI/2020/WindowsSettings",
+            value,
+        )
+    }
 
-            if rc.exists() {
-                println!("{:?}", rc);
-                kits.push(rc.parent().unwrap().to_owned());
-            }
+    /// Let GDI scale the contents of DPI unaware applications (Windows 10 1703 and later).
+    ///
+    /// This adds `<gdiScaling>true</gdiScaling>` to the `<windowsSettings>` of the
+    /// application manifest, see [`set_segment_heap()`] on how manifests are merged.
+    ///
+    /// [`set_segment_heap()`]: #method.set_segment_heap
+    pub fn set_gdi_scaling(&mut self, enable: bool) -> &mut Self {
+        let value = if enable { Some("true") } else { None };
+        self.set_windows_setting(
+            "gdiScaling",
+            "http://schemas.microsoft.com/SMI/2017/WindowsSettings",
+            value,
+        )
+    }
 
-            if let Ok(bin) = p.join("bin").read_dir() {
-                for e in bin.filter_map(|e| e.ok()) {
-                    let p = if cfg!(target_arch = "x86_64") {
-                        e.path().join(r"x64\rc.exe")
-                    } else {
-                        e.path().join(r"x86\rc.exe")
-                    };
-                    if p.exists() {
-                        println!("{:?}", p);
-                        kits.push(p.parent().unwrap().to_owned());
-                    }
-                }
-            }
-        }
+    /// Allow paths longer than `MAX_PATH` (Windows 10 1607 and later).
+    ///
+    /// This adds `<longPathAware>true</longPathAware>` to the `<windowsSettings>` of
+    /// the application manifest, see [`set_segment_heap()`] on how manifests are
+    /// merged. Long paths also have to be enabled in the system settings.
+    ///
+    /// [`set_segment_heap()`]: #method.set_segment_heap
+    pub fn set_long_path_aware(&mut self, enable: bool) -> &mut Self {
+        let value = if enable { Some("true") } else { None };
+        self.set_windows_setting(
+            "longPathAware",
+            "http://schemas.microsoft.com/SMI/2016/WindowsSettings",
+            value,
+        )
     }
-    if kits.is_empty() {
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
-            "Can not find Windows SDK",
-        ));
+
+    /// Set the code page of the `-A` Windows API functions (Windows 10 1903 and later).
+    ///
+    /// This adds `<activeCodePage>` to the `<windowsSettings>` of the application
+    /// manifest, see [`set_segment_heap()`] on how manifests are merged. With
+    /// [`ActiveCodePage::Utf8`] the C runtime and the `-A` functions use UTF-8,
+    /// independent of the system locale.
+    ///
+    /// [`set_segment_heap()`]: #method.set_segment_heap
+    /// [`ActiveCodePage::Utf8`]: enum.ActiveCodePage.html#variant.Utf8
+    pub fn set_active_code_page(&mut self, code_page: ActiveCodePage) -> &mut Self {
+        self.set_windows_setting(
+            "activeCodePage",
+            "http://schemas.microsoft.com/SMI/2019/WindowsSettings",
+            Some(code_page.value()),
+        )
     }
 
-    Ok(kits)
-}
+    /// Declare the DPI awareness of the application in the manifest.
+    ///
+    /// This adds both the legacy `<dpiAware>` and the `<dpiAwareness>` element to the
+    /// `<windowsSettings>` of the application manifest, see [`set_segment_heap()`] on
+    /// how manifests are merged. Windows 10 1607 and later only read `<dpiAwareness>`,
+    /// older versions only `<dpiAware>`. E.g., [`DpiAwareness::PerMonitorV2`] emits
+    /// `<dpiAware>true/pm</dpiAware>` and `<dpiAwareness>PerMonitorV2,PerMonitor</dpiAwareness>`,
+    /// so Windows versions without per monitor v2 support fall back to per monitor.
+    /// It is an error if a manifest set with [`set_manifest()`] already declares
+    /// its DPI awareness.
+    ///
+    /// [`set_segment_heap()`]: #method.set_segment_heap
+    /// [`set_manifest()`]: #method.set_manifest
+    /// [`DpiAwareness::PerMonitorV2`]: enum.DpiAwareness.html#variant.PerMonitorV2
+    pub fn set_dpi_awareness(&mut self, awareness: DpiAwareness) -> &mut Self {
+        self.dpi_awareness = Some(awareness);
+        self.set_windows_setting(
+            "dpiAware",
+            "http://schemas.microsoft.com/SMI/2005/WindowsSettings",
+            Some(awareness.dpi_aware_value()),
+        )
+        .set_windows_setting(
+            "dpiAwareness",
+            "http://schemas.microsoft.com/SMI/2016/WindowsSettings",
+            Some(awareness.dpi_awareness_value()),
+        )
+    }
 
-fn parse_cargo_toml(props: &mut HashMap<String, String>) -> io::Result<()> {
-    let cargo = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("Cargo.toml");
-    let mut f = fs::File::open(cargo)?;
-    let mut cargo_toml = String::new();
-    f.read_to_string(&mut cargo_toml)?;
-    if let Ok(ml) = cargo_toml.parse::<toml::Value>() {
-        if let Some(pkg) = ml.get("package") {
-            if let Some(pkg) = pkg.get("metadata") {
-                if let Some(pkg) = pkg.get("winres") {
-                    if let Some(pkg) = pkg.as_table() {
-                        for (k, v) in pkg {
-                            // println!("{} {}", k ,v);
-                            if let Some(v) = v.as_str() {
-                                props.insert(k.clone(), v.to_string());
-                            } else {
-                                println!("package.metadata.winres.{} is not a string", k);
-                            }
-                        }
-                    } else {
-                        println!("package.metadata.winres is not a table");
-                    }
-                } else {
-                    println!("package.metadata.winres does not exist");
+    /// Request the privileges the application runs with from UAC.
+    ///
+    /// This adds a `<trustInfo>` element with `<requestedExecutionLevel>` to the
+    /// application manifest, see [`set_segment_heap()`] on how manifests are merged.
+    /// It is an error if a manifest set with [`set_manifest()`] already contains
+    /// a `<requestedExecutionLevel>`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut res = winres::WindowsResource::new();
+    /// res.set_requested_execution_level(winres::ExecutionLevel::RequireAdministrator);
+    /// ```
+    ///
+    /// [`set_segment_heap()`]: #method.set_segment_heap
+    /// [`set_manifest()`]: #method.set_manifest
+    pub fn set_requested_execution_level(&mut self, level: ExecutionLevel) -> &mut Self {
+        self.execution_level = Some(level);
+        self
+    }
+
+    /// Declare a dependency on a side-by-side assembly in the manifest.
+    ///
+    /// This adds a `<dependency>` element with the `<assemblyIdentity>` of the
+    /// assembly to the application manifest, see [`set_segment_heap()`] on how
+    /// manifests are merged. The processor architecture and the language are `*`.
+    /// Adding an assembly with the same name again replaces it. It is an error if a
+    /// manifest set with [`set_manifest()`] already depends on an assembly of that name.
+    ///
+    /// [`set_segment_heap()`]: #method.set_segment_heap
+    /// [`set_manifest()`]: #method.set_manifest
+    pub fn add_dependent_assembly(
+        &mut self,
+        name: &str,
+        version: &str,
+        public_key_token: &str,
+    ) -> &mut Self {
+        self.dependent_assemblies.retain(|a| a.name != name);
+        self.dependent_assemblies.push(DependentAssembly {
+            name: name.to_string(),
+            version: version.to_string(),
+            public_key_token: public_key_token.to_string(),
+        });
+        self
+    }
+
+    /// Use version 6 of the common controls, which draws them with visual styles.
+    ///
+    /// Without it, buttons and other controls look like on Windows 95. This is
+    /// the same as
+    ///
+    /// ```nocheck
+    /// res.add_dependent_assembly("Microsoft.Windows.Common-Controls", "6.0.0.0", "6595b64144ccf1df");
+    /// ```
+    ///
+    /// see [`add_dependent_assembly()`].
+    ///
+    /// [`add_dependent_assembly()`]: #method.add_dependent_assembly
+    pub fn enable_common_controls_v6(&mut self) -> &mut Self {
+        self.add_dependent_assembly(
+            "Microsoft.Windows.Common-Controls",
+            "6.0.0.0",
+            "6595b64144ccf1df",
+        )
+    }
+
+    /// Additionally embed the DPI awareness as an `RCDATA` resource with the given name ID.
+    ///
+    /// Windows only reads the manifest. This marker is for applications (or the GUI
+    /// framework they use) that decide on their scaling behavior themselves, before
+    /// any window is created, and want to use the same declaration as the manifest.
+    /// The resource contains the lowercase name of the awareness, e.g.,
+    /// `permonitorv2`, as a NUL terminated ASCII string; it can be read with
+    /// `FindResourceW(h_instance, name_id, RT_RCDATA)` and `LoadResource`.
+    ///
+    /// The marker is only emitted when [`set_dpi_awareness()`] was called.
+    ///
+    /// [`set_dpi_awareness()`]: #method.set_dpi_awareness
+    pub fn set_dpi_awareness_marker(&mut self, name_id: &str) -> &mut Self {
+        self.dpi_marker = Some(name_id.to_string());
+        self
+    }
+
+    /// Replace or (with `None`) remove an element of `<windowsSettings>`
+    fn set_windows_setting(
+        &mut self,
+        name: &'static str,
+        namespace: &'static str,
+        value: Option<&str>,
+    ) -> &mut Self {
+        self.windows_settings.retain(|s| s.name != name);
+        if let Some(value) = value {
+            self.windows_settings.push(WindowsSetting {
+                name,
+                namespace,
+                value: value.to_string(),
+            });
+        }
+        self
+    }
+
+    /// Add a manifest with the given name ID.
+    ///
+    /// Most binaries need only one manifest, set with [`set_manifest()`] or
+    /// [`set_manifest_file()`]. Use this function for additional manifests, e.g.,
+    /// for an embedded component with its own assembly identity. The manifests are
+    /// `RT_MANIFEST` resources, or the type set with [`set_manifest_resource()`].
+    /// Adding a manifest with the same name ID again replaces it.
+    /// Manifest settings like [`set_dpi_awareness()`] are only merged into the
+    /// manifest set with [`set_manifest()`].
+    ///
+    /// [`set_manifest()`]: #method.set_manifest
+    /// [`set_manifest_file()`]: #method.set_manifest_file
+    /// [`set_manifest_resource()`]: #method.set_manifest_resource
+    /// [`set_dpi_awareness()`]: #method.set_dpi_awareness
+    pub fn add_manifest(&mut self, name_id: u16, source: ManifestSource) -> &mut Self {
+        self.set_manifest_source(Some(name_id), source)
+    }
+
+    /// Replace the manifest with the name ID, `None` is the application manifest
+    fn set_manifest_source(&mut self, name_id: Option<u16>, source: ManifestSource) -> &mut Self {
+        match self.manifests.iter_mut().find(|(id, _)| *id == name_id) {
+            Some(m) => m.1 = source,
+            None => self.manifests.push((name_id, source)),
+        }
+        self
+    }
+
+    /// The application manifest with all manifest settings merged in
+    fn primary_manifest(&self) -> io::Result<Option<ManifestSource>> {
+        let manifest = self
+            .manifests
+            .iter()
+            .find(|(id, _)| id.is_none())
+            .map(|(_, source)| source);
+        if self.windows_settings.is_empty()
+            && self.execution_level.is_none()
+            && self.dependent_assemblies.is_empty()
+        {
+            return Ok(manifest.cloned());
+        }
+        let manifest = match manifest {
+            Some(ManifestSource::File(file)) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "Manifest settings cannot be merged into the manifest file {}, use set_manifest() instead",
+                        file
+                    ),
+                ));
+            }
+            Some(ManifestSource::Inline(m)) => Some(m),
+            None => None,
+        };
+
+        let mut manifest = match manifest {
+            Some(m) => m.clone(),
+            None => "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<assembly xmlns=\"urn:schemas-microsoft-com:asm.v1\" manifestVersion=\"1.0\">\n</assembly>\n".to_string(),
+        };
+
+        // the elements that are added at the end of <assembly>
+        let mut elements = String::new();
+        if let Some(level) = self.execution_level {
+            if manifest.contains("<requestedExecutionLevel") {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "The manifest already contains a <requestedExecutionLevel>, it cannot be set with set_requested_execution_level()",
+                ));
+            }
+            elements.push_str(&format!(
+                "<trustInfo xmlns=\"urn:schemas-microsoft-com:asm.v3\">\n<security>\n<requestedPrivileges>\n    <requestedExecutionLevel level=\"{}\" uiAccess=\"false\"/>\n</requestedPrivileges>\n</security>\n</trustInfo>\n",
+                level.value()
+            ));
+        }
+
+        for a in &self.dependent_assemblies {
+            if manifest.contains(&format!("name=\"{}\"", a.name)) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "The manifest already depends on {}, it cannot be added with add_dependent_assembly()",
+                        a.name
+                    ),
+                ));
+            }
+            elements.push_str(&format!(
+                "<dependency>\n<dependentAssembly>\n    <assemblyIdentity type=\"win32\" name=\"{}\" version=\"{}\" processorArchitecture=\"*\" publicKeyToken=\"{}\" language=\"*\"/>\n</dependentAssembly>\n</dependency>\n",
+                a.name, a.version, a.public_key_token
+            ));
+        }
+
+        if !self.windows_settings.is_empty() {
+            let mut settings = String::new();
+            for s in &self.windows_settings {
+                if manifest.contains(&format!("<{}>", s.name))
+                    || manifest.contains(&format!("<{} ", s.name))
+                {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!(
+                            "The manifest already contains a <{}>, it cannot be merged with the manifest settings",
+                            s.name
+                        ),
+                    ));
                 }
+                settings.push_str(&format!(
+                    "    <{0} xmlns=\"{1}\">{2}</{0}>\n",
+                    s.name, s.namespace, s.value
+                ));
+            }
+            if let Some(pos) = manifest.find("</windowsSettings>") {
+                manifest.insert_str(pos, &settings);
             } else {
-                println!("package.metadata does not exist");
+                elements.push_str(&format!(
+                    "<application xmlns=\"urn:schemas-microsoft-com:asm.v3\">\n<windowsSettings>\n{}</windowsSettings>\n</application>\n",
+                    settings
+                ));
+            }
+        }
+
+        if !elements.is_empty() {
+            match manifest.rfind("</assembly>") {
+                Some(pos) => manifest.insert_str(pos, &elements),
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "Manifest settings cannot be merged, the manifest has no </assembly> element",
+                    ));
+                }
+            }
+        }
+        Ok(Some(ManifestSource::Inline(manifest)))
+    }
+
+    /// Write a manifest resource
+    fn write_manifest<W: Write>(
+        &self,
+        f: &mut W,
+        id: u64,
+        source: &ManifestSource,
+    ) -> io::Result<()> {
+        match source {
+            ManifestSource::Inline(manf) => {
+                writeln!(f, "{} {}", id, self.manifest_type)?;
+                writeln!(f, "{{")?;
+                for line in manf.lines() {
+                    writeln!(f, "\" {} \"", escape_string(line.trim()))?;
+                }
+                writeln!(f, "}}")
+            }
+            ManifestSource::File(manf) => {
+                let manf = self.resolve_path(manf);
+                writeln!(
+                    f,
+                    "{} {} \"{}\"",
+                    id,
+                    self.manifest_type,
+                    escape_string(&manf.to_string_lossy())
+                )
+            }
+        }
+    }
+
+    /// Write the application manifest to a file instead of embedding it.
+    ///
+    /// With `true`, the manifest set with [`set_manifest()`] or [`set_manifest_file()`],
+    /// including the manifest settings, is not written to the resource file. Instead
+    /// it is written to `<binary>.manifest` in the output directory, where `<binary>`
+    /// is the `OriginalFilename` property, or the package name with `.exe` (`.dll`
+    /// for a [`CrateType::DynamicLibrary`]). You have to deploy this file next to the
+    /// binary, Windows only reads it if the binary has no embedded manifest. This is
+    /// useful to change the manifest without rebuilding. Manifests added with
+    /// [`add_manifest()`] are still embedded.
+    ///
+    /// [`set_manifest()`]: #method.set_manifest
+    /// [`set_manifest_file()`]: #method.set_manifest_file
+    /// [`add_manifest()`]: #method.add_manifest
+    /// [`CrateType::DynamicLibrary`]: enum.CrateType.html#variant.DynamicLibrary
+    pub fn set_manifest_external(&mut self, external: bool) -> &mut Self {
+        self.manifest_external = external;
+        self
+    }
+
+    /// The path of the external manifest, see [`set_manifest_external()`]
+    ///
+    /// [`set_manifest_external()`]: #method.set_manifest_external
+    fn external_manifest_path(&self) -> PathBuf {
+        let binary = match self.properties.get("OriginalFilename") {
+            Some(name) => name.clone(),
+            None => {
+                let name = env::var("CARGO_PKG_NAME").unwrap_or_else(|_| "app".to_string());
+                match self.crate_type {
+                    CrateType::DynamicLibrary => format!("{}.dll", name),
+                    _ => format!("{}.exe", name),
+                }
+            }
+        };
+        PathBuf::from(&self.output_directory).join(format!("{}.manifest", binary))
+    }
+
+    /// Write the application manifest next to the resource file, see
+    /// [`set_manifest_external()`]
+    ///
+    /// [`set_manifest_external()`]: #method.set_manifest_external
+    fn write_external_manifest(&self) -> io::Result<()> {
+        let path = self.external_manifest_path();
+        match self.primary_manifest()? {
+            Some(ManifestSource::Inline(xml)) => fs::write(&path, xml)?,
+            Some(ManifestSource::File(file)) => {
+                fs::copy(self.resolve_path(&file), &path)?;
+            }
+            None => return Ok(()),
+        }
+        self.log(
+            LogLevel::Normal,
+            &format!(
+                "Manifest written to {}, deploy it next to the binary",
+                path.display()
+            ),
+        );
+        Ok(())
+    }
+
+    /// Set the name ID and the type ID of the manifest resource.
+    ///
+    /// By default the manifest is an `RT_MANIFEST (24)` resource with the name ID
+    /// of [`set_manifest_id()`]. Use this function for side-by-side assemblies and
+    /// isolation aware components that load their manifest from a different resource.
+    ///
+    /// [`set_manifest_id()`]: #method.set_manifest_id
+    pub fn set_manifest_resource(&mut self, name_id: u16, type_id: u16) -> &mut Self {
+        self.manifest_id = Some(name_id);
+        self.manifest_type = type_id;
+        self
+    }
+
+    /// Set the name ID of the manifest resource.
+    ///
+    /// The default is `1` (`CREATEPROCESS_MANIFEST_RESOURCE_ID`), which Windows reads
+    /// for executables, or `2` (`ISOLATIONAWARE_MANIFEST_RESOURCE_ID`) for a
+    /// [`CrateType::DynamicLibrary`] or when `FILETYPE` is `2` (`VFT_DLL`).
+    ///
+    /// [`CrateType::DynamicLibrary`]: enum.CrateType.html#variant.DynamicLibrary
+    pub fn set_manifest_id(&mut self, name_id: u16) -> &mut Self {
+        self.manifest_id = Some(name_id);
+        self
+    }
+
+    /// The name ID of the manifest resource, see [`set_manifest_id()`]
+    ///
+    /// [`set_manifest_id()`]: #method.set_manifest_id
+    fn manifest_id(&self) -> u16 {
+        match self.manifest_id {
+            Some(id) => id,
+            None if self.crate_type == CrateType::DynamicLibrary => 2,
+            // VFT_DLL, how DLLs were configured before set_crate_type()
+            None if self.version_info.get(&VersionInfo::FILETYPE) == Some(&2) => 2,
+            None => 1,
+        }
+    }
+
+    /// Set the path to the windres executable.
+    ///
+    /// On Windows the default is `windres.exe`. On other hosts the MinGW cross
+    /// compiler for the target is looked up in `PATH`, e.g.,
+    /// `x86_64-w64-mingw32-windres`, then `windres` and `llvm-windres`.
+    pub fn set_windres_path(&mut self, path: &str) -> &mut Self {
+        self.windres_path = path.to_string();
+        self
+    }
+
+    /// Set the path to the ar executable.
+    ///
+    /// The default is found like for [`set_windres_path()`], e.g.,
+    /// `x86_64-w64-mingw32-ar`, `ar` or `llvm-ar`.
+    ///
+    /// [`set_windres_path()`]: #method.set_windres_path
+    pub fn set_ar_path(&mut self, path: &str) -> &mut Self {
+        self.ar_path = path.to_string();
+        self
+    }
+
+    /// Set the path to the llvm-rc executable.
+    ///
+    /// The default is `llvm-rc`, which is looked up in `%PATH%`.
+    pub fn set_llvm_rc_path(&mut self, path: &str) -> &mut Self {
+        self.llvm_rc_path = path.to_string();
+        self
+    }
+
+    /// Select the resource compiler for the MSVC target.
+    ///
+    /// With [`ResourceCompiler::Auto`] `rc.exe` of the Windows SDK is used, or
+    /// `llvm-rc` (see [`set_llvm_rc_path()`]) when no SDK with `rc.exe` is found.
+    /// This allows cross compiling to `*-pc-windows-msvc` on a Linux or macOS host
+    /// with only the LLVM toolchain installed. `llvm-rc` writes a `.res` file, which
+    /// is linked like the output of `rc.exe`.
+    ///
+    /// With [`add_toolkit_include()`] `llvm-rc` only gets the include directories
+    /// of an SDK set with [`set_sdk()`].
+    ///
+    /// This setting has no effect for the GNU toolkit.
+    ///
+    /// [`ResourceCompiler::Auto`]: enum.ResourceCompiler.html#variant.Auto
+    /// [`set_llvm_rc_path()`]: #method.set_llvm_rc_path
+    /// [`add_toolkit_include()`]: #method.add_toolkit_include
+    /// [`set_sdk()`]: #method.set_sdk
+    pub fn set_resource_compiler(&mut self, compiler: ResourceCompiler) -> &mut Self {
+        self.resource_compiler = compiler;
+        self
+    }
+
+    /// Add the include directories of the toolkit to the resource compiler's search path.
+    ///
+    /// This is needed for resource scripts (or content added with [`append_rc_content()`])
+    /// that `#include <windows.h>` or `<winresrc.h>` to use symbolic constants like
+    /// `VS_VERSION_INFO`, `VFT_APP` or `WS_CHILD`.
+    ///
+    /// For MSVC the `um` and `shared` directories of the Windows SDK, and `ucrt` if present,
+    /// are added. Older SDKs without these subdirectories get the `Include` directory itself.
+    ///
+    /// For the GNU toolkit the MinGW `include` directory is located relative to the
+    /// `windres` executable, i.e., `<bin>/../include` or `<bin>/../<triple>/include`
+    /// for a target prefixed `windres` like `x86_64-w64-mingw32-windres`.
+    ///
+    /// [`append_rc_content()`]: #method.append_rc_content
+    pub fn add_toolkit_include(&mut self, add: bool) -> &mut Self {
+        self.add_toolkit_include = add;
+        self
+    }
+
+    /// Add a directory to the include search path of the resource compiler.
+    ///
+    /// The include directories are searched in this order:
+    ///
+    ///  1. directories added with [`add_include_path_front()`], the last added first
+    ///  2. the crate's root directory, i.e., `CARGO_MANIFEST_DIR`
+    ///  3. directories added with this function, in the order they were added
+    ///  4. the toolkit include directories, see [`add_toolkit_include()`]
+    ///
+    /// [`add_include_path_front()`]: #method.add_include_path_front
+    /// [`add_toolkit_include()`]: #method.add_toolkit_include
+    pub fn add_include_path(&mut self, path: &str) -> &mut Self {
+        self.include_paths.push(PathBuf::from(path));
+        self
+    }
+
+    /// Add a directory to the front of the include search path.
+    ///
+    /// Headers in this directory shadow headers of the same name in the crate's root,
+    /// the other include paths and the toolkit. See [`add_include_path()`] for the
+    /// complete search order.
+    ///
+    /// [`add_include_path()`]: #method.add_include_path
+    pub fn add_include_path_front(&mut self, path: &str) -> &mut Self {
+        self.include_paths_front.insert(0, PathBuf::from(path));
+        self
+    }
+
+    /// Resolve the path of an input file the way the resource compiler will see it.
+    ///
+    /// Absolute paths are used as they are. Relative paths are relative to the crate's
+    /// root directory, i.e., `CARGO_MANIFEST_DIR`, and not to the working directory of
+    /// the build script or the compiler. This applies to icons, manifest files,
+    /// resource files set with [`set_resource_file()`], other embedded files and
+    /// include paths. The resolved paths are written to the resource file, so inputs
+    /// produced by an earlier build step can be referenced by their absolute path
+    /// with both toolkits.
+    ///
+    /// [`set_resource_file()`]: #method.set_resource_file
+    pub fn resolve_path<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        let path = path.as_ref();
+        if path.is_absolute() {
+            return path.to_path_buf();
+        }
+        match env::var_os("CARGO_MANIFEST_DIR") {
+            Some(root) => PathBuf::from(root).join(path),
+            None => path.to_path_buf(),
+        }
+    }
+
+    /// Create a thin archive for the GNU toolkit.
+    ///
+    /// By default `ar` copies the compiled resource object into `libresource.a`.
+    /// A thin archive (`ar rcsT`) only references the object, which saves I/O when
+    /// large payloads are embedded. The object file (`resource.o`) is then required
+    /// until the final link and must stay in the output directory; winres never
+    /// deletes it.
+    ///
+    /// This setting has no effect for the MSVC toolkit.
+    pub fn set_thin_archive(&mut self, thin: bool) -> &mut Self {
+        self.thin_archive = thin;
+        self
+    }
+
+    /// Keep the compiled resource for post-build steps.
+    ///
+    /// For the MSVC toolkit the compiled resource is additionally copied to
+    /// `<name>.res`, which tools like `cvtres.exe` or installers expect. The GNU
+    /// toolkit always keeps the object file `<name>.o` next to the library. The path
+    /// is returned by [`res_path()`].
+    ///
+    /// [`res_path()`]: #method.res_path
+    pub fn set_keep_res(&mut self, keep: bool) -> &mut Self {
+        self.keep_res = keep;
+        self
+    }
+
+    /// Link the compiled resource directly instead of wrapping it in a library.
+    ///
+    /// `link.exe` accepts `.res` files as inputs, as does the MinGW linker for the object
+    /// file compiled by `windres`. With this setting the MSVC toolkit writes `<name>.res`,
+    /// the GNU toolkit skips `ar`, and the file is passed to the linker with
+    /// `cargo:rustc-link-arg=`. No archiving tool is required, and for MSVC the resource
+    /// is guaranteed to be linked even if no symbol references it.
+    ///
+    /// `cargo:rustc-link-arg` requires Cargo 1.50 or later and applies to all linked
+    /// targets of the package, therefore this is not the default.
+    pub fn set_prefer_native_res(&mut self, prefer: bool) -> &mut Self {
+        self.prefer_native_res = prefer;
+        self
+    }
+
+    /// Choose how the GNU toolkit links the compiled resource.
+    ///
+    /// With [`GnuLinkMode::DirectObject`] the object file `<name>.o` is passed to the
+    /// linker with `cargo:rustc-link-arg=` and `ar` is neither needed nor run. This
+    /// is like [`set_prefer_native_res()`], but only for the GNU toolkit. The default
+    /// is [`GnuLinkMode::StaticArchive`].
+    ///
+    /// [`GnuLinkMode::DirectObject`]: enum.GnuLinkMode.html#variant.DirectObject
+    /// [`GnuLinkMode::StaticArchive`]: enum.GnuLinkMode.html#variant.StaticArchive
+    /// [`set_prefer_native_res()`]: #method.set_prefer_native_res
+    pub fn set_gnu_link_mode(&mut self, mode: GnuLinkMode) -> &mut Self {
+        self.gnu_link_mode = mode;
+        self
+    }
+
+    /// Whether the GNU toolkit links the object file without an archive
+    fn gnu_direct_object(&self) -> bool {
+        self.prefer_native_res || self.gnu_link_mode == GnuLinkMode::DirectObject
+    }
+
+    /// The compiled resource of the MSVC toolkit, see [`set_prefer_native_res()`]
+    ///
+    /// [`set_prefer_native_res()`]: #method.set_prefer_native_res
+    fn msvc_output(&self, output_dir: &str) -> PathBuf {
+        let extension = if self.prefer_native_res { "res" } else { "lib" };
+        PathBuf::from(output_dir).join(format!("{}.{}", self.output_name, extension))
+    }
+
+    /// The path of the compiled resource that is kept by [`set_keep_res()`]
+    ///
+    /// This is `<output directory>/<name>.res` for the MSVC toolkit and
+    /// `<output directory>/<name>.o` for the GNU toolkit, see [`set_output_name()`].
+    /// Returns `None` if [`set_keep_res()`] is not set or the target is neither.
+    ///
+    /// [`set_keep_res()`]: #method.set_keep_res
+    /// [`set_output_name()`]: #method.set_output_name
+    pub fn res_path(&self) -> Option<PathBuf> {
+        if !self.keep_res {
+            return None;
+        }
+        let extension = match std::env::var("CARGO_CFG_TARGET_ENV")
+            .as_ref()
+            .map(|s| s.as_str())
+        {
+            Ok("msvc") => "res",
+            Ok("gnu") => "o",
+            _ => return None,
+        };
+        Some(
+            PathBuf::from(&self.output_directory)
+                .join(format!("{}.{}", self.output_name, extension)),
+        )
+    }
+
+    /// Set the base name of the compiled resource object and library.
+    ///
+    /// The generated resource script is written to `<name>.rc`. The GNU toolkit writes
+    /// `<name>.o` and `lib<name>.a` and links `static=<name>`, the MSVC toolkit writes
+    /// `<name>.lib` and links `dylib=<name>`. The default is `resource`. Use different
+    /// names when a build script compiles more than one resource into the same output
+    /// directory, otherwise the files overwrite each other.
+    pub fn set_output_name(&mut self, name: &str) -> &mut Self {
+        self.output_name = name.to_string();
+        self
+    }
+
+    /// Set the kind of crate the resource is compiled for.
+    ///
+    /// For [`CrateType::DynamicLibrary`] the `FILETYPE` is set to `VFT_DLL (0x2)`.
+    ///
+    /// A [`CrateType::StaticLibrary`] is not linked by rustc, the resource has to be
+    /// linked into the final executable or DLL by the C or C++ build. Therefore no
+    /// `cargo:rustc-link-*` directives are printed. Instead the compiled resource is
+    /// left in the output directory at a predictable location:
+    ///
+    ///  - MSVC: `<output directory>\<name>.lib`, which actually is a `.res` file
+    ///    that can be passed to `link.exe` like any other input file
+    ///  - GNU: `<output directory>/<name>.o`, see [`set_output_name()`]; link the
+    ///    object file, not the archive, as linkers skip archive members whose
+    ///    symbols are never referenced
+    ///
+    /// Use [`set_output_directory()`] to put it where the C build expects it.
+    ///
+    /// [`CrateType::DynamicLibrary`]: enum.CrateType.html#variant.DynamicLibrary
+    /// [`CrateType::StaticLibrary`]: enum.CrateType.html#variant.StaticLibrary
+    /// [`set_output_name()`]: #method.set_output_name
+    /// [`set_output_directory()`]: #method.set_output_directory
+    pub fn set_crate_type(&mut self, crate_type: CrateType) -> &mut Self {
+        self.crate_type = crate_type;
+        if crate_type == CrateType::DynamicLibrary {
+            self.version_info.insert(VersionInfo::FILETYPE, 2);
+        }
+        self
+    }
+
+    /// Set how much is printed to the build script output.
+    ///
+    /// This covers all output of winres, for both toolkits and the parsing of
+    /// `Cargo.toml`. The cargo directives required for linking are always printed.
+    /// Build script output is only shown by cargo when the build fails or with `-vv`.
+    ///
+    /// | Level     | Output                                                          |
+    /// |-----------|-----------------------------------------------------------------|
+    /// | `Quiet`   | only cargo link directives                                      |
+    /// | `Normal`  | problems, compiler messages and the selected resource compiler  |
+    /// | `Verbose` | full compiler output, SDK discovery and include paths           |
+    pub fn set_log_level(&mut self, level: LogLevel) -> &mut Self {
+        self.log_level = level;
+        self
+    }
+
+    /// Only write the resource file, without running the resource compiler.
+    ///
+    /// [`compile()`] writes `<name>.rc` to the output directory and prints its path,
+    /// but neither runs a compiler nor prints `cargo:rustc-link-*` directives. This
+    /// works for any target and without a toolkit, so the generated version info and
+    /// manifest can be checked on Linux or macOS. The settings and images are still
+    /// checked like for a real compilation.
+    ///
+    /// [`compile()`]: #method.compile
+    pub fn set_dry_run(&mut self, dry_run: bool) -> &mut Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Skip the resource compiler when nothing changed since the last compilation.
+    ///
+    /// [`compile()`] stores a hash of the resource script, the input files and the
+    /// toolkit settings in `<name>.stamp` in the output directory. When the hash is
+    /// unchanged and the compiled resource still exists, the compiler is not run
+    /// again and only the cargo directives are printed. Files that are included by
+    /// the resource script with `#include`, other than with [`include_rc_file()`],
+    /// are not part of the hash. This is enabled by default.
+    ///
+    /// [`compile()`]: #method.compile
+    /// [`include_rc_file()`]: #method.include_rc_file
+    pub fn set_incremental(&mut self, incremental: bool) -> &mut Self {
+        self.incremental = incremental;
+        self
+    }
+
+    /// Fail when the resource compiler reports warnings.
+    ///
+    /// The warnings of `rc.exe`, `windres` and `llvm-rc`, e.g., for duplicate resource
+    /// IDs, are printed but do not stop the build by default. With this setting
+    /// [`compile()`] returns [`WinresError::Compiler`] with the warnings, which is
+    /// useful in CI.
+    ///
+    /// [`compile()`]: #method.compile
+    /// [`WinresError::Compiler`]: enum.WinresError.html#variant.Compiler
+    pub fn set_warnings_as_errors(&mut self, warnings_as_errors: bool) -> &mut Self {
+        self.warnings_as_errors = warnings_as_errors;
+        self
+    }
+
+    /// Whether the compiler run failed, because of its exit status or, with
+    /// [`set_warnings_as_errors()`], because of warnings
+    ///
+    /// [`set_warnings_as_errors()`]: #method.set_warnings_as_errors
+    fn compiler_failed(&self, output: &process::Output, diagnostics: &[Diagnostic]) -> bool {
+        !output.status.success()
+            || (self.warnings_as_errors
+                && diagnostics.iter().any(|d| d.severity == Severity::Warning))
+    }
+
+    /// Write the date of [`set_file_date()`] into a compiled resource
+    ///
+    /// [`set_file_date()`]: #method.set_file_date
+    fn apply_file_date(&self, compiled: &Path) -> io::Result<()> {
+        if let Some(date) = self.file_date {
+            let mut data = fs::read(compiled)?;
+            if patch_file_date(&mut data, date) {
+                fs::write(compiled, data)?;
             }
+        }
+        Ok(())
+    }
+
+    /// Print a message if the log level permits it
+    fn log(&self, level: LogLevel, message: &str) {
+        if level <= self.log_level {
+            println!("{}", message);
+        }
+    }
+
+    /// Print the compiler output, all of it for `Verbose` and only when there
+    /// is something to report otherwise
+    fn log_compiler_output(
+        &self,
+        name: &str,
+        output: &process::Output,
+        diagnostics: &[Diagnostic],
+    ) {
+        let level = if diagnostics.is_empty() && output.status.success() {
+            LogLevel::Verbose
         } else {
-            println!("package does not exist");
+            LogLevel::Normal
+        };
+        for (stream, text) in [("Output", &output.stdout), ("Error", &output.stderr)].iter() {
+            let text = String::from_utf8_lossy(text);
+            if !text.trim().is_empty() {
+                self.log(level, &format!("{} {}:\n{}\n------", name, stream, text));
+            }
         }
-    } else {
-        println!("TOML parsing error")
     }
-    Ok(())
-}
 
-fn escape_string(string: &str) -> String {
-    let mut escaped = String::new();
-    for chr in string.chars() {
-        // In quoted RC strings, double-quotes are escaped by using two
-        // consecutive double-quotes.  Other characters are escaped in the
-        // usual C way using backslashes.
-        match chr {
-            '"' => escaped.push_str("\"\""),
-            '\'' => escaped.push_str("\\'"),
-            '\\' => escaped.push_str("\\\\"),
-            '\n' => escaped.push_str("\\n"),
-            '\t' => escaped.push_str("\\t"),
-            '\r' => escaped.push_str("\\r"),
-            _ => escaped.push(chr),
-        };
+    /// Set how the output directory is printed in the `cargo:rustc-link-search` directive.
+    ///
+    /// Some linkers, e.g., LLVM's `lld` in cross compilation setups, do not like
+    /// backslashes in library search paths. [`LinkPathStyle::ForwardSlashes`]
+    /// normalizes the path separators.
+    ///
+    /// [`LinkPathStyle::ForwardSlashes`]: enum.LinkPathStyle.html#variant.ForwardSlashes
+    pub fn set_link_path_style(&mut self, style: LinkPathStyle) -> &mut Self {
+        self.link_path_style = style;
+        self
+    }
+
+    /// The output directory in the form used for `cargo:rustc-link-*` directives
+    fn link_path(&self, path: &str) -> String {
+        match self.link_path_style {
+            LinkPathStyle::Native => path.to_string(),
+            LinkPathStyle::ForwardSlashes => path.replace('\\', "/"),
+        }
+    }
+
+    /// Write a resource file with the set values
+    ///
+    /// The content is the one returned by [`generate_rc_content()`], encoded as set
+    /// with [`set_rc_encoding()`].
+    ///
+    /// [`generate_rc_content()`]: #method.generate_rc_content
+    /// [`set_rc_encoding()`]: #method.set_rc_encoding
+    pub fn write_resource_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let rc = self.generate_rc_content()?;
+        match self.rc_encoding {
+            RcEncoding::Utf8 => fs::write(path, rc),
+            RcEncoding::Utf16Le => {
+                let mut data = vec![0xff, 0xfe];
+                for unit in rc.encode_utf16() {
+                    data.extend_from_slice(&unit.to_le_bytes());
+                }
+                fs::write(path, data)
+            }
+        }
+    }
+
+    /// The content of the resource file, without writing it to disk
+    ///
+    /// This is the exact text [`write_resource_file()`] and [`compile()`] write,
+    /// including the edits of [`set_rc_transform()`]. Use it to inspect the resource
+    /// script in tests or to pass it to a resource compiler of your own. A resource
+    /// file set with [`set_resource_file()`] is not read.
+    ///
+    /// ```rust
+    /// let mut res = winres::WindowsResource::new();
+    /// res.set("CompanyName", "Contoso \"Ltd\"");
+    /// let rc = res.generate_rc_content().unwrap();
+    /// assert!(rc.contains(r#"VALUE "CompanyName", "Contoso ""Ltd""""#));
+    /// ```
+    ///
+    /// [`write_resource_file()`]: #method.write_resource_file
+    /// [`compile()`]: #method.compile
+    /// [`set_rc_transform()`]: #method.set_rc_transform
+    /// [`set_resource_file()`]: #method.set_resource_file
+    pub fn generate_rc_content(&self) -> io::Result<String> {
+        let rc = self.generate_resource_string()?;
+        Ok(match self.rc_transform.as_ref() {
+            Some(RcTransform(transform)) => transform(rc),
+            None => rc,
+        })
+    }
+
+    /// Quote and escape a string value
+    ///
+    /// With [`RcEncoding::Utf8`], strings with characters outside the Basic
+    /// Multilingual Plane, e.g., most emoji, are written as wide strings with the
+    /// surrogate pairs escaped, see [`escape_wide_string()`].
+    ///
+    /// [`RcEncoding::Utf8`]: enum.RcEncoding.html#variant.Utf8
+    /// [`escape_wide_string()`]: fn.escape_wide_string.html
+    fn quote_string(&self, string: &str) -> String {
+        if self.rc_encoding == RcEncoding::Utf8 && string.chars().any(|c| c > '\u{ffff}') {
+            format!("L\"{}\"", escape_wide_string(string))
+        } else {
+            format!("\"{}\"", escape_string(string))
+        }
+    }
+
+    /// Write the `VERSIONINFO` resource
+    fn write_version_info<W: Write>(&self, f: &mut W) -> io::Result<()> {
+        writeln!(f, "1 VERSIONINFO")?;
+        let mut version_info: Vec<_> = self.version_info.iter().collect();
+        version_info.sort();
+        for (k, v) in version_info {
+            match *k {
+                VersionInfo::FILEVERSION | VersionInfo::PRODUCTVERSION => {
+                    let (major, minor, patch, build) = unpack_version(*v);
+                    writeln!(f, "{:?} {}, {}, {}, {}", k, major, minor, patch, build)?
+                }
+                _ => writeln!(f, "{:?} {:#x}", k, v)?,
+            };
+        }
+        let mut blocks = Vec::new();
+        if !self
+            .language_strings
+            .iter()
+            .any(|l| l.language == self.language && l.codepage == self.codepage)
+        {
+            blocks.push((self.language, self.codepage, None));
+        }
+        for l in &self.language_strings {
+            blocks.push((l.language, l.codepage, Some(&l.properties)));
+        }
+
+        writeln!(f, "{{\nBLOCK \"StringFileInfo\"\n{{")?;
+        for &(language, codepage, overrides) in &blocks {
+            writeln!(f, "BLOCK \"{:04x}{:04x}\"\n{{", language, codepage)?;
+            let mut properties = self.properties.clone();
+            if let Some(overrides) = overrides {
+                properties.extend(overrides.iter().map(|(k, v)| (k.clone(), v.clone())));
+            }
+            for k in &self.force_emit {
+                properties.entry(k.clone()).or_default();
+            }
+            let mut properties: Vec<_> = properties.into_iter().collect();
+            properties.sort();
+            for (k, v) in properties {
+                if !v.is_empty() || self.force_emit.contains(&k) {
+                    writeln!(
+                        f,
+                        "VALUE \"{}\", {}",
+                        escape_string(&k),
+                        self.quote_string(&v)
+                    )?;
+                }
+            }
+            writeln!(f, "}}")?;
+        }
+        writeln!(f, "}}")?;
+
+        let translations: Vec<String> = blocks
+            .iter()
+            .map(|&(language, codepage, _)| format!("{:#x}, {:#06x}", language, codepage))
+            .collect();
+        writeln!(f, "BLOCK \"VarFileInfo\" {{")?;
+        writeln!(f, "VALUE \"Translation\", {}", translations.join(", "))?;
+        writeln!(f, "}}\n}}")?;
+        Ok(())
+    }
+
+    /// Generate the content of the resource file
+    ///
+    /// The output is deterministic: version info fields and string properties
+    /// are written in a fixed order.
+    pub(crate) fn generate_resource_string(&self) -> io::Result<String> {
+        let manifest = self.primary_manifest()?;
+        let mut f: Vec<u8> = Vec::new();
+
+        // use UTF8 as an encoding
+        // this makes it easier since in rust all string are UTF8
+        if self.rc_encoding == RcEncoding::Utf8 {
+            writeln!(f, "#pragma code_page(65001)")?;
+        }
+        if self.emit_version_info {
+            self.write_version_info(&mut f)?;
+        }
+        for icon in self.ordered_icons() {
+            writeln!(
+                f,
+                "{} ICON \"{}\"",
+                escape_string(&icon.name_id),
+                escape_string(&self.resolve_path(&icon.path).to_string_lossy())
+            )?;
+        }
+        for bitmap in &self.bitmaps {
+            writeln!(
+                f,
+                "{} BITMAP \"{}\"",
+                escape_string(&bitmap.name_id),
+                escape_string(&self.resolve_path(&bitmap.path).to_string_lossy())
+            )?;
+        }
+        for cursor in &self.cursors {
+            writeln!(
+                f,
+                "{} CURSOR \"{}\"",
+                escape_string(&cursor.name_id),
+                escape_string(&self.resolve_path(&cursor.path).to_string_lossy())
+            )?;
+        }
+        if !self.strings.is_empty() {
+            let mut strings: Vec<_> = self.strings.iter().collect();
+            strings.sort_by_key(|(id, _)| *id);
+            writeln!(f, "STRINGTABLE")?;
+            if self.language != 0 {
+                writeln!(
+                    f,
+                    "LANGUAGE {:#x}, {:#x}",
+                    self.language & 0x3ff,
+                    self.language >> 10
+                )?;
+            }
+            writeln!(f, "{{")?;
+            for (id, text) in strings {
+                writeln!(f, "{}, {}", id, self.quote_string(text))?;
+            }
+            writeln!(f, "}}")?;
+        }
+        for (name_id, entries) in &self.accelerators {
+            writeln!(f, "{} ACCELERATORS\n{{", escape_string(name_id))?;
+            for entry in entries {
+                writeln!(f, "{}", entry.to_rc())?;
+            }
+            writeln!(f, "}}")?;
+        }
+        if let Some(manf) = manifest.as_ref().filter(|_| !self.manifest_external) {
+            self.write_manifest(&mut f, u64::from(self.manifest_id()), manf)?;
+        }
+        for (id, manf) in &self.manifests {
+            if let Some(id) = id {
+                self.write_manifest(&mut f, u64::from(*id), manf)?;
+            }
+        }
+        for res in &self.resources {
+            writeln!(
+                f,
+                "{} {} \"{}\"",
+                escape_string(&res.name_id),
+                res.type_id,
+                escape_string(&self.resolve_path(&res.path).to_string_lossy())
+            )?;
+        }
+        if let (Some(awareness), Some(id)) = (self.dpi_awareness, self.dpi_marker.as_ref()) {
+            writeln!(
+                f,
+                "{} RCDATA {{ \"{}\\0\" }}",
+                escape_string(id),
+                awareness.name()
+            )?;
+        }
+        if let Some(id) = self.app_user_model_id.as_ref() {
+            writeln!(
+                f,
+                "{} RCDATA {{ L\"{}\\0\" }}",
+                APP_USER_MODEL_ID_RESOURCE,
+                escape_string(id)
+            )?;
+        }
+        writeln!(f, "{}", self.append_rc_content)?;
+        Ok(String::from_utf8(f).unwrap())
+    }
+
+    /// Set a path to an already existing resource file.
+    ///
+    /// We will neither modify this file nor parse its contents. This function
+    /// simply replaces the internaly generated resource file that is passed to
+    /// the compiler. You can use this function to write a resource file yourself.
+    pub fn set_resource_file<'a>(&mut self, path: &'a str) -> &mut Self {
+        self.rc_file = Some(path.to_string());
+        self
+    }
+
+    /// Append an additional snippet to the generated rc file.
+    ///
+    /// # Example
+    ///
+    /// Define a menu resource:
+    ///
+    /// ```rust
+    /// # extern crate winres;
+    /// # if cfg!(target_os = "windows") {
+    ///     let mut res = winres::WindowsResource::new();
+    ///     res.append_rc_content(r##"sample MENU
+    /// {
+    ///     MENUITEM "&Soup", 100
+    ///     MENUITEM "S&alad", 101
+    ///     POPUP "&Entree"
+    ///     {
+    ///          MENUITEM "&Fish", 200
+    ///          MENUITEM "&Chicken", 201, CHECKED
+    ///          POPUP "&Beef"
+    ///          {
+    ///               MENUITEM "&Steak", 301
+    ///               MENUITEM "&Prime Rib", 302
+    ///          }
+    ///     }
+    ///     MENUITEM "&Dessert", 103
+    /// }"##);
+    /// #    res.compile()?;
+    /// # }
+    /// # Ok::<_, std::io::Error>(())
+    /// ```
+    pub fn append_rc_content<'a>(&mut self, content: &'a str) -> &mut Self {
+        if !(self.append_rc_content.ends_with('\n') || self.append_rc_content.is_empty()) {
+            self.append_rc_content.push('\n');
+        }
+        self.append_rc_content.push_str(content);
+        self
+    }
+
+    /// Include another resource script in the generated rc file.
+    ///
+    /// This appends an `#include "path"` directive, so it is ordered with the
+    /// snippets added with [`append_rc_content()`] and written after the version
+    /// info. A relative path is looked up by the resource compiler in the include
+    /// directories, which contain the crate's root directory, see
+    /// [`add_include_path()`].
+    ///
+    /// [`append_rc_content()`]: #method.append_rc_content
+    /// [`add_include_path()`]: #method.add_include_path
+    pub fn include_rc_file(&mut self, path: &str) -> &mut Self {
+        let file = self.resolve_path(path);
+        self.input_files.push(file);
+        self.append_rc_content(&format!("#include \"{}\"", path.replace('\\', "\\\\")))
+    }
+
+    /// Edit the generated resource file before it is written.
+    ///
+    /// The function is called with the complete resource file, after
+    /// [`append_rc_content()`], and returns the content that is written and compiled.
+    /// Use this for edits the other functions do not cover, e.g., to rewrite a path
+    /// or to remove a pragma. It is not called for a resource file set with
+    /// [`set_resource_file()`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut res = winres::WindowsResource::new();
+    /// res.set_rc_transform(|rc| rc.replace("#pragma code_page(65001)\n", ""));
+    /// ```
+    ///
+    /// [`append_rc_content()`]: #method.append_rc_content
+    /// [`set_resource_file()`]: #method.set_resource_file
+    pub fn set_rc_transform<F>(&mut self, transform: F) -> &mut Self
+    where
+        F: Fn(String) -> String + Send + Sync + 'static,
+    {
+        self.rc_transform = Some(RcTransform(Box::new(transform)));
+        self
+    }
+
+    /// Set the encoding of the generated resource file.
+    ///
+    /// The default [`RcEncoding::Utf8`] relies on `#pragma code_page(65001)`, which
+    /// some older versions of `rc.exe` do not handle, so non-ASCII strings end up
+    /// garbled. [`RcEncoding::Utf16Le`] writes the file as UTF-16LE with a byte
+    /// order mark instead, which every `rc.exe` reads. `windres` does not support
+    /// UTF-16 input, so only use it with `rc.exe` or `llvm-rc`.
+    ///
+    /// With UTF-8, string values with characters outside the Basic Multilingual
+    /// Plane, like most emoji, are written as wide strings with `\xHHHH` escapes of
+    /// the surrogate pairs, which compilers without four byte UTF-8 support read.
+    ///
+    /// [`RcEncoding::Utf8`]: enum.RcEncoding.html#variant.Utf8
+    /// [`RcEncoding::Utf16Le`]: enum.RcEncoding.html#variant.Utf16Le
+    pub fn set_rc_encoding(&mut self, encoding: RcEncoding) -> &mut Self {
+        self.rc_encoding = encoding;
+        self
+    }
+
+    /// Override the output directoy.
+    ///
+    /// As a default, we use `%OUT_DIR%` set by cargo, but it may be necessary to override the
+    /// the setting.
+    pub fn set_output_directory<'a>(&mut self, path: &'a str) -> &mut Self {
+        self.output_directory = path.to_string();
+        self
+    }
+
+    /// All include directories in the order they are searched
+    fn include_dirs(&self, toolkit: Vec<PathBuf>) -> Vec<PathBuf> {
+        let mut dirs: Vec<PathBuf> = self
+            .include_paths_front
+            .iter()
+            .map(|p| self.resolve_path(p))
+            .collect();
+        dirs.push(PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()));
+        dirs.extend(self.include_paths.iter().map(|p| self.resolve_path(p)));
+        for dir in toolkit {
+            self.log(
+                LogLevel::Verbose,
+                &format!("Adding toolkit include: {}", dir.display()),
+            );
+            dirs.push(dir);
+        }
+        dirs
+    }
+
+    /// The path of a GNU tool, in the toolkit directory if it is found there
+    ///
+    /// The tool is not run in the toolkit directory, because relative paths in its
+    /// arguments would then be resolved against it.
+    fn gnu_tool(&self, name: &str) -> PathBuf {
+        let in_toolkit = self.toolkit_path.join(name);
+        if !self.toolkit_path.as_os_str().is_empty() && in_toolkit.is_file() {
+            absolute_path(&in_toolkit)
+        } else {
+            PathBuf::from(name)
+        }
+    }
+
+    fn windres_command(&self, input: &Path, output: &Path) -> process::Command {
+        let mut command = process::Command::new(self.gnu_tool(&self.windres_path));
+
+        let toolkit = if self.add_toolkit_include {
+            mingw_include_dirs(&self.windres_path, &self.toolkit_path)
+        } else {
+            Vec::new()
+        };
+        for dir in self.include_dirs(toolkit) {
+            let mut arg = OsString::from("-I");
+            arg.push(absolute_path(&dir));
+            command.arg(arg);
+        }
+
+        command.arg(absolute_path(input)).arg(absolute_path(output));
+        command
+    }
+
+    fn ar_command(&self, libname: &Path, object: &Path) -> process::Command {
+        let mut command = process::Command::new(self.gnu_tool(&self.ar_path));
+        command
+            .arg(if self.thin_archive { "rcsT" } else { "rsc" })
+            .arg(absolute_path(libname))
+            .arg(absolute_path(object));
+        command
+    }
+
+    /// Find `rc.exe` in the toolkit candidates and the toolkit path
+    fn rc_exe(&self) -> io::Result<PathBuf> {
+        let bins: Vec<String> = sdk_archs()
+            .iter()
+            .map(|arch| format!(r"bin\{}\rc.exe", arch))
+            .collect();
+        let mut tried = Vec::new();
+        for toolkit in self
+            .toolkit_candidates
+            .iter()
+            .chain(std::iter::once(&self.toolkit_path))
+        {
+            let candidates = std::iter::once(toolkit.join("rc.exe"))
+                .chain(bins.iter().map(|bin| toolkit.join(bin)));
+            for rc_exe in candidates {
+                if rc_exe.is_file() {
+                    return Ok(rc_exe);
+                }
+                tried.push(rc_exe.display().to_string());
+            }
+        }
+        if self.toolkit_candidates.is_empty() {
+            // leave the error to the command, as before
+            return Ok(self.toolkit_path.join(&bins[0]));
+        }
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Could not find rc.exe, tried: {}", tried.join(", ")),
+        ))
+    }
+
+    fn rc_command(&self, rc_exe: &Path, input: &Path, output: &Path) -> process::Command {
+        let mut command = process::Command::new(rc_exe);
+
+        let toolkit = if !self.add_toolkit_include {
+            Vec::new()
+        } else if let Some(sdk) = self.sdk.as_ref().filter(|sdk| sdk.rc_exe() == rc_exe) {
+            sdk.include_roots.clone()
+        } else {
+            win_sdk_include_dirs(rc_exe)
+        };
+        for dir in self.include_dirs(toolkit) {
+            command.arg(format!("/I{}", dir.display()));
+        }
+
+        command
+            .arg(format!("/fo{}", output.display()))
+            .arg(format!("{}", input.display()));
+        command
+    }
+
+    /// The resource compiler that is used for the MSVC target, either
+    /// `ResourceCompiler::Rc` or `ResourceCompiler::LlvmRc`
+    fn msvc_resource_compiler(&self) -> ResourceCompiler {
+        match self.resource_compiler {
+            ResourceCompiler::Auto => {
+                let rc_found = self.rc_exe().map(|p| p.is_file()).unwrap_or(false);
+                let llvm_rc = Path::new(&self.llvm_rc_path);
+                if !rc_found && (llvm_rc.is_file() || find_in_path(&self.llvm_rc_path).is_some()) {
+                    ResourceCompiler::LlvmRc
+                } else {
+                    ResourceCompiler::Rc
+                }
+            }
+            compiler => compiler,
+        }
+    }
+
+    fn llvm_rc_command(&self, input: &Path, output: &Path) -> process::Command {
+        let mut command = process::Command::new(&self.llvm_rc_path);
+
+        let toolkit = match self.sdk.as_ref() {
+            Some(sdk) if self.add_toolkit_include => sdk.include_roots.clone(),
+            _ => Vec::new(),
+        };
+        for dir in self.include_dirs(toolkit) {
+            command.arg("-I").arg(dir);
+        }
+
+        command.arg("/FO").arg(output).arg(input);
+        command
+    }
+
+    fn compile_with_toolkit_gnu<'a>(
+        &self,
+        input: &'a str,
+        output_dir: &'a str,
+    ) -> Result<CompileOutput, WinresError> {
+        let output = PathBuf::from(output_dir).join(format!("{}.o", self.output_name));
+        let input = PathBuf::from(input);
+        let status = run_toolkit(self.windres_command(&input, &output))?;
+
+        let diagnostics: Vec<Diagnostic> = String::from_utf8_lossy(&status.stderr)
+            .lines()
+            .filter_map(parse_windres_line)
+            .collect();
+        self.log_compiler_output("Windres", &status, &diagnostics);
+        if self.compiler_failed(&status, &diagnostics) {
+            return Err(WinresError::compiler(status, diagnostics));
+        }
+        self.apply_file_date(&output)?;
+        let stdout = String::from_utf8_lossy(&status.stdout).into_owned();
+        let stderr = String::from_utf8_lossy(&status.stderr).into_owned();
+
+        if self.gnu_direct_object() {
+            return Ok(CompileOutput {
+                directives: self.link_directives(output_dir, "static", &self.output_name, &output),
+                artifact: Some(output),
+                diagnostics,
+                stdout,
+                stderr,
+            });
+        }
+
+        let libname = PathBuf::from(output_dir).join(format!("lib{}.a", self.output_name));
+        // a thin archive cannot be updated into a regular one, so start fresh
+        if libname.exists() {
+            fs::remove_file(&libname)?;
+        }
+        let archive = run_toolkit(self.ar_command(&libname, &output))?;
+        if !archive.status.success() {
+            return Err(WinresError::Archiver {
+                status: archive.status,
+                stderr: String::from_utf8_lossy(&archive.stderr).into_owned(),
+            });
+        }
+
+        Ok(CompileOutput {
+            directives: self.link_directives(output_dir, "static", &self.output_name, &output),
+            artifact: Some(output),
+            diagnostics,
+            stdout,
+            stderr,
+        })
+    }
+
+    /// The cargo directives to link the compiled resource
+    ///
+    /// For a static library nothing can be linked, instead `artifact` has to be picked
+    /// up by the C build.
+    fn link_directives(
+        &self,
+        output_dir: &str,
+        kind: &str,
+        lib: &str,
+        artifact: &Path,
+    ) -> Vec<String> {
+        if self.crate_type == CrateType::StaticLibrary {
+            self.log(
+                LogLevel::Normal,
+                &format!(
+                    "Compiled resource for static library: {}",
+                    artifact.display()
+                ),
+            );
+            return Vec::new();
+        }
+        // only the GNU toolkit links a static library
+        let direct = if kind == "static" {
+            self.gnu_direct_object()
+        } else {
+            self.prefer_native_res
+        };
+        if direct {
+            return vec![format!(
+                "cargo:rustc-link-arg={}",
+                self.link_path(&artifact.to_string_lossy())
+            )];
+        }
+        vec![
+            format!(
+                "cargo:rustc-link-search=native={}",
+                self.link_path(output_dir)
+            ),
+            format!("cargo:rustc-link-lib={}={}", kind, lib),
+        ]
+    }
+
+    /// Check that all icons, bitmaps and cursors exist and are `.ico`, `.bmp` resp.
+    /// `.cur` files, and that the files of other resources exist, the resource
+    /// compiler reports them with a less helpful message
+    fn check_resource_files(&self) -> Result<(), WinresError> {
+        let kinds: [(&[Image], &[u8], &str); 3] = [
+            (&self.icons, &[0, 0, 1, 0], "an ICO file"),
+            (&self.bitmaps, b"BM", "a BMP file"),
+            (&self.cursors, &[0, 0, 2, 0], "a CUR file"),
+        ];
+        let mut invalid = Vec::new();
+        for &(images, magic, format) in kinds.iter() {
+            for image in images {
+                let path = self.resolve_path(&image.path);
+                let mut header = [0; 4];
+                let problem =
+                    match fs::File::open(&path).and_then(|mut f| f.read_exact(&mut header)) {
+                        Ok(()) if header.starts_with(magic) => continue,
+                        Ok(()) => format!("not {}, found {}", format, file_format(&header)),
+                        Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+                            "file not found".to_string()
+                        }
+                        Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                            format!("not {}, the file is too short", format)
+                        }
+                        Err(e) => e.to_string(),
+                    };
+                invalid.push((path, problem));
+            }
+        }
+        for res in &self.resources {
+            let path = self.resolve_path(&res.path);
+            if !path.is_file() {
+                invalid.push((path, "file not found".to_string()));
+            }
+        }
+        if invalid.is_empty() {
+            Ok(())
+        } else {
+            Err(WinresError::InvalidResourceFiles(invalid))
+        }
+    }
+
+    /// Warn about icons that only contain sizes Windows has to scale
+    fn check_icon_sizes(&self) {
+        for icon in &self.icons {
+            let path = self.resolve_path(&icon.path);
+            let sizes = match fs::read(&path).and_then(|data| ico_sizes(&data)) {
+                Ok(sizes) => sizes,
+                Err(_) => continue,
+            };
+            if !sizes.is_empty()
+                && !sizes
+                    .iter()
+                    .any(|&(w, h)| w == h && STANDARD_ICON_SIZES.contains(&w))
+            {
+                let found: Vec<String> =
+                    sizes.iter().map(|(w, h)| format!("{}x{}", w, h)).collect();
+                self.log(LogLevel::Normal, &format!(
+                    "cargo:warning=icon {} only contains non-standard sizes ({}), Windows will scale it poorly; recommended sizes are 16, 20, 24, 32, 40, 48, 64, 96, 128 and 256",
+                    path.display(),
+                    found.join(", ")
+                ));
+            }
+        }
+    }
+
+    /// Write the generated resource file to the output directory, unless a resource file
+    /// was set, and return the path of the script for the compiler
+    fn resource_script(&self) -> io::Result<String> {
+        let rc = self.resource_script_path();
+        if self.rc_file.is_none() {
+            self.write_resource_file(&rc)?;
+        }
+        if self.manifest_external {
+            self.write_external_manifest()?;
+        }
+        Ok(rc.to_str().unwrap().to_string())
+    }
+
+    /// The path of the resource script passed to the compiler
+    fn resource_script_path(&self) -> PathBuf {
+        match self.rc_file.as_ref() {
+            Some(s) => self.resolve_path(s),
+            None => PathBuf::from(&self.output_directory).join(format!("{}.rc", self.output_name)),
+        }
+    }
+
+    /// The resource compiler [`compile()`] would run for the current target
+    ///
+    /// This runs the same lookup of the Windows SDK, the GNU toolkit or `llvm-rc`
+    /// as [`compile()`], without compiling anything, and fails with
+    /// [`WinresError::ToolkitNotFound`] if the executable does not exist. Use it to
+    /// show the compiler to the user or to fail early.
+    ///
+    /// [`compile()`]: #method.compile
+    /// [`WinresError::ToolkitNotFound`]: enum.WinresError.html#variant.ToolkitNotFound
+    pub fn resolve_compiler(&self) -> Result<ResolvedCompiler, WinresError> {
+        let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+        if !target_os.is_empty() && target_os != "windows" {
+            return Err(WinresError::UnsupportedTarget(target_name(&target_os)));
+        }
+        self.resolve_compiler_for(&cargo_env("CARGO_CFG_TARGET_ENV")?)
+    }
+
+    /// [`resolve_compiler()`] for the given `target_env`
+    ///
+    /// [`resolve_compiler()`]: #method.resolve_compiler
+    fn resolve_compiler_for(&self, target_env: &str) -> Result<ResolvedCompiler, WinresError> {
+        let (kind, path) = match target_env {
+            "gnu" => (CompilerKind::Windres, self.gnu_tool(&self.windres_path)),
+            "msvc" => match self.msvc_resource_compiler() {
+                ResourceCompiler::LlvmRc => {
+                    (CompilerKind::LlvmRc, PathBuf::from(&self.llvm_rc_path))
+                }
+                _ => (
+                    CompilerKind::Rc,
+                    self.rc_exe()
+                        .map_err(|e| WinresError::ToolkitNotFound(e.to_string()))?,
+                ),
+            },
+            _ => return Err(WinresError::UnsupportedTarget(target_name(target_env))),
+        };
+        let found = if path.is_file() {
+            Some(path.clone())
+        } else if path.components().count() == 1 {
+            find_in_path(&path.to_string_lossy())
+        } else {
+            None
+        };
+        match found {
+            Some(path) => Ok(ResolvedCompiler { kind, path }),
+            None => Err(WinresError::ToolkitNotFound(format!(
+                "The resource compiler {} was not found",
+                path.display()
+            ))),
+        }
+    }
+
+    /// The command lines [`compile()`] would run, without running them
+    ///
+    /// This returns the resource compiler invocation for the current target, and for
+    /// the GNU toolkit the `ar` invocation, joined with `&&`. Arguments containing
+    /// spaces are quoted, so the result can be pasted into a shell to see the
+    /// raw output of the compiler. Nothing is written to disk, so for a generated
+    /// resource file run [`write_resource_file()`] first.
+    ///
+    /// [`compile()`]: #method.compile
+    /// [`write_resource_file()`]: #method.write_resource_file
+    pub fn compiler_command_line(&self) -> io::Result<String> {
+        let rc = self.resource_script_path();
+        let output_dir = PathBuf::from(&self.output_directory);
+        let target_env = std::env::var("CARGO_CFG_TARGET_ENV").unwrap_or_default();
+        match target_env.as_str() {
+            "gnu" => {
+                let object = output_dir.join(format!("{}.o", self.output_name));
+                let windres = command_line(&self.windres_command(&rc, &object));
+                if self.gnu_direct_object() {
+                    return Ok(windres);
+                }
+                let libname = output_dir.join(format!("lib{}.a", self.output_name));
+                Ok(format!(
+                    "{} && {}",
+                    windres,
+                    command_line(&self.ar_command(&libname, &object))
+                ))
+            }
+            "msvc" => {
+                let output = self.msvc_output(&self.output_directory);
+                if self.msvc_resource_compiler() == ResourceCompiler::LlvmRc {
+                    return Ok(command_line(&self.llvm_rc_command(&rc, &output)));
+                }
+                Ok(command_line(&self.rc_command(
+                    &self.rc_exe()?,
+                    &rc,
+                    &output,
+                )))
+            }
+            _ => Err(WinresError::UnsupportedTarget(target_name(&target_env)).into()),
+        }
+    }
+
+    /// Run the resource compiler
+    ///
+    /// This function generates a resource file from the settings or
+    /// uses an existing resource file and passes it to the resource compiler
+    /// of your toolkit.
+    ///
+    /// Further more we will print the correct statements for
+    /// `cargo:rustc-link-lib=` and `cargo:rustc-link-search` on the console,
+    /// so that the cargo build script can link the compiled resource file.
+    ///
+    /// When the target OS is not Windows, e.g., for Linux or WASM, nothing is compiled
+    /// and a `cargo:warning=` is printed, so cross-platform crates do not need to wrap
+    /// the call in a `cfg!(windows)` check. Use [`set_strict()`] to get an error instead.
+    ///
+    /// The returned [`WinresError`] tells a missing toolkit apart from a failed
+    /// compiler run, which contains the output of the resource compiler.
+    ///
+    /// A `cargo:rerun-if-changed` directive is printed for every icon, bitmap, cursor,
+    /// manifest file, resource file and for `Cargo.toml`, so cargo compiles the
+    /// resource again when one of them changes. Note that cargo then no longer reruns
+    /// the build script for changes of other files in the package; print directives
+    /// for the files your build script reads as well.
+    ///
+    /// [`set_strict()`]: #method.set_strict
+    /// [`WinresError`]: enum.WinresError.html
+    pub fn compile(&self) -> Result<(), WinresError> {
+        self.compile_verbose().map(|_| ())
+    }
+
+    /// Compile several resources in parallel
+    ///
+    /// Each resource is compiled like with [`compile()`] on its own thread, which
+    /// speeds up build scripts that embed many resources, e.g., one per binary of a
+    /// workspace. The resources must not share an output file, so give every resource
+    /// in the same output directory a distinct name with [`set_output_name()`].
+    ///
+    /// All compilations run to completion, the returned [`WinresError::Failed`] has
+    /// the error of every resource that failed.
+    ///
+    /// [`compile()`]: #method.compile
+    /// [`set_output_name()`]: #method.set_output_name
+    /// [`WinresError::Failed`]: enum.WinresError.html#variant.Failed
+    pub fn compile_all(resources: &[WindowsResource]) -> Result<(), WinresError> {
+        let mut outputs = std::collections::HashSet::new();
+        for res in resources {
+            let output = PathBuf::from(&res.output_directory).join(&res.output_name);
+            if !outputs.insert(output.clone()) {
+                return Err(WinresError::Io(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "More than one resource compiles to {}, use set_output_name() to separate them",
+                        output.display()
+                    ),
+                )));
+            }
+        }
+
+        let results: Vec<Result<(), WinresError>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = resources
+                .iter()
+                .map(|res| scope.spawn(move || res.compile()))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle.join().unwrap_or_else(|_| {
+                        Err(WinresError::Io(io::Error::other(
+                            "resource compilation panicked",
+                        )))
+                    })
+                })
+                .collect()
+        });
+
+        let errors: Vec<(String, WinresError)> = resources
+            .iter()
+            .zip(results)
+            .filter_map(|(res, result)| result.err().map(|e| (res.output_name.clone(), e)))
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(WinresError::Failed(errors))
+        }
+    }
+
+    /// Run the resource compiler and return its warnings
+    ///
+    /// Works like [`compile()`], but the messages of the resource compiler are
+    /// parsed into [`Diagnostic`]s. On success the warnings and notes are returned,
+    /// on failure the error message contains all collected diagnostics.
+    ///
+    /// [`compile()`]: #method.compile
+    /// [`Diagnostic`]: struct.Diagnostic.html
+    pub fn compile_verbose(&self) -> Result<Vec<Diagnostic>, WinresError> {
+        let output = self.compile_silent()?;
+        for directive in &output.directives {
+            println!("{}", directive);
+        }
+        Ok(output.diagnostics)
+    }
+
+    /// Run the resource compiler without printing the cargo directives
+    ///
+    /// Works like [`compile_verbose()`], but the `cargo:rustc-link-*` directives are
+    /// returned in the [`CompileOutput`] instead of printed. This is for crates that
+    /// wrap winres and print the directives themselves. Other messages are still
+    /// printed according to [`set_log_level()`], use `LogLevel::Quiet` and the
+    /// compiler output in [`CompileOutput`] to present it in your own way.
+    ///
+    /// [`compile_verbose()`]: #method.compile_verbose
+    /// [`CompileOutput`]: struct.CompileOutput.html
+    /// [`set_log_level()`]: #method.set_log_level
+    pub fn compile_silent(&self) -> Result<CompileOutput, WinresError> {
+        for (level, message) in &self.log_messages {
+            self.log(*level, message);
+        }
+        if self.dry_run {
+            self.validate()?;
+            self.check_resource_files()?;
+            let rc = self.resource_script()?;
+            self.log(
+                LogLevel::Normal,
+                &format!("Dry run, resource file not compiled: {}", rc),
+            );
+            return Ok(CompileOutput {
+                artifact: None,
+                directives: Vec::new(),
+                diagnostics: Vec::new(),
+                stdout: String::new(),
+                stderr: String::new(),
+            });
+        }
+        let target_os = std::env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+        if self.skip_target_os(&target_os)? {
+            return Ok(CompileOutput {
+                artifact: None,
+                directives: Vec::new(),
+                diagnostics: Vec::new(),
+                stdout: String::new(),
+                stderr: String::new(),
+            });
+        }
+        self.validate()?;
+        self.check_resource_files()?;
+        self.check_icon_sizes();
+        let rc = self.resource_script()?;
+
+        let target_env = cargo_env("CARGO_CFG_TARGET_ENV")?;
+        let stamp_path =
+            PathBuf::from(&self.output_directory).join(format!("{}.stamp", self.output_name));
+        let stamp = format!("{:016x}", self.input_hash(&rc, &target_env)?);
+        if self.incremental && fs::read_to_string(&stamp_path).ok().as_ref() == Some(&stamp) {
+            if let Some(mut output) = self.unchanged_output(&target_env) {
+                self.log(
+                    LogLevel::Verbose,
+                    "Resource unchanged since the last compilation, skipping the compiler",
+                );
+                output.directives.extend(self.rerun_directives());
+                return Ok(output);
+            }
+        }
+        // a failed compilation might leave a broken artifact behind
+        let _ = fs::remove_file(&stamp_path);
+        let mut output = match target_env.as_str() {
+            "gnu" => self.compile_with_toolkit_gnu(rc.as_str(), &self.output_directory),
+            "msvc" => match self.msvc_resource_compiler() {
+                ResourceCompiler::LlvmRc => {
+                    self.compile_with_toolkit_llvm(rc.as_str(), &self.output_directory)
+                }
+                _ => self.compile_with_toolkit_msvc(rc.as_str(), &self.output_directory),
+            },
+            _ => Err(WinresError::UnsupportedTarget(target_name(&target_env))),
+        }?;
+        if self.incremental {
+            fs::write(&stamp_path, stamp)?;
+        }
+        output.directives.extend(self.rerun_directives());
+        Ok(output)
+    }
+
+    /// The hash of everything that affects the compiled resource, see
+    /// [`set_incremental()`]
+    ///
+    /// [`set_incremental()`]: #method.set_incremental
+    fn input_hash(&self, rc: &str, target_env: &str) -> io::Result<u64> {
+        let settings = format!(
+            "{} {:?} {:?} {} {} {} {:?} {:?} {:?} {:?} {:?} {} {} {} {:?} {:?}",
+            target_env,
+            self.toolkit_path,
+            self.sdk,
+            self.windres_path,
+            self.ar_path,
+            self.llvm_rc_path,
+            self.resource_compiler,
+            self.include_paths_front,
+            self.include_paths,
+            self.crate_type,
+            self.gnu_link_mode,
+            self.add_toolkit_include,
+            self.prefer_native_res,
+            self.thin_archive,
+            self.keep_res,
+            self.file_date,
+        );
+        let mut data = settings.into_bytes();
+        data.extend(fs::read(rc)?);
+        for file in self.input_paths() {
+            data.extend(file.to_string_lossy().as_bytes());
+            data.extend(fs::read(&file).unwrap_or_default());
+        }
+        Ok(content_hash(&data))
+    }
+
+    /// The output of the last compilation, if its files still exist
+    fn unchanged_output(&self, target_env: &str) -> Option<CompileOutput> {
+        let output_dir = &self.output_directory;
+        let (kind, artifact, files) = match target_env {
+            "gnu" => {
+                let object = PathBuf::from(output_dir).join(format!("{}.o", self.output_name));
+                let mut files = vec![object.clone()];
+                if !self.gnu_direct_object() {
+                    files
+                        .push(PathBuf::from(output_dir).join(format!("lib{}.a", self.output_name)));
+                }
+                ("static", object, files)
+            }
+            "msvc" => {
+                let output = self.msvc_output(output_dir);
+                ("dylib", output.clone(), vec![output])
+            }
+            _ => return None,
+        };
+        if !files.iter().all(|f| f.is_file()) {
+            return None;
+        }
+        Some(CompileOutput {
+            directives: self.link_directives(output_dir, kind, &self.output_name, &artifact),
+            artifact: Some(artifact),
+            diagnostics: Vec::new(),
+            stdout: String::new(),
+            stderr: String::new(),
+        })
+    }
+
+    /// The `cargo:rerun-if-changed` directives for the input files
+    ///
+    /// These are the images, manifest files, included resource files and
+    /// `Cargo.toml`. Files in the output directory are written on every run, e.g., by
+    /// [`set_icon_from_bytes()`], and are left out, else cargo would always rerun
+    /// the build script.
+    ///
+    /// [`set_icon_from_bytes()`]: #method.set_icon_from_bytes
+    fn rerun_directives(&self) -> Vec<String> {
+        let output_dir = self.resolve_path(&self.output_directory);
+        self.input_paths()
+            .into_iter()
+            .filter(|file| !file.starts_with(&output_dir))
+            .map(|file| format!("cargo:rerun-if-changed={}", file.display()))
+            .collect()
+    }
+
+    /// The input files of the resource, without duplicates
+    fn input_paths(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = Vec::new();
+        if let Some(rc) = self.rc_file.as_ref() {
+            files.push(self.resolve_path(rc));
+        }
+        for image in self.icons.iter().chain(&self.bitmaps).chain(&self.cursors) {
+            files.push(self.resolve_path(&image.path));
+        }
+        for (_, manifest) in &self.manifests {
+            if let ManifestSource::File(file) = manifest {
+                files.push(self.resolve_path(file));
+            }
+        }
+        for res in &self.resources {
+            files.push(self.resolve_path(&res.path));
+        }
+        files.extend(self.input_files.iter().cloned());
+        if let Some(root) = env::var_os("CARGO_MANIFEST_DIR") {
+            files.push(PathBuf::from(root).join("Cargo.toml"));
+        }
+
+        let mut unique = Vec::new();
+        for file in files {
+            if !unique.contains(&file) {
+                unique.push(file);
+            }
+        }
+        unique
+    }
+
+    /// Check the resource script with `windres`, regardless of the target
+    ///
+    /// The resource file is written like in [`compile()`] and compiled to a throw-away
+    /// `resource_lint.res` in the output directory. Nothing is linked and no cargo
+    /// directives are printed. Use this in CI to make sure resources stay portable
+    /// to the GNU toolkit when building for MSVC. The `windres` executable is
+    /// configured with [`set_windres_path()`] and [`set_toolkit_path()`].
+    ///
+    /// [`compile()`]: #method.compile
+    /// [`set_windres_path()`]: #method.set_windres_path
+    /// [`set_toolkit_path()`]: #method.set_toolkit_path
+    pub fn lint_with_windres(&self) -> io::Result<()> {
+        let rc = self.resource_script()?;
+        let output = PathBuf::from(&self.output_directory).join("resource_lint.res");
+        let status = self.windres_command(Path::new(&rc), &output).output()?;
+        let _ = fs::remove_file(&output);
+        if !status.status.success() {
+            let diagnostics: Vec<Diagnostic> = String::from_utf8_lossy(&status.stderr)
+                .lines()
+                .filter_map(parse_windres_line)
+                .collect();
+            return Err(WinresError::compiler(status, diagnostics).into());
+        }
+        Ok(())
+    }
+
+    /// Add the resources to an existing executable or DLL
+    ///
+    /// This is for binaries that are not linked by cargo, e.g., a prebuilt launcher.
+    /// The resource file is compiled to `<name>.res` in the output directory with the
+    /// resource compiler of the target toolkit, or of the host toolkit outside of a
+    /// build script, and every resource in it is written into `pe_path` with the
+    /// `UpdateResourceW` function of Windows. Existing resources with the same type,
+    /// name ID and language are replaced, all others are kept. Note that icons are
+    /// stored as separate images with numeric IDs, which might replace images of
+    /// icons that are already in the binary.
+    ///
+    /// Nothing is linked and no cargo directives are printed. This requires a
+    /// Windows host; on other hosts an error is returned after compiling.
+    pub fn embed_into<P: AsRef<Path>>(&self, pe_path: P) -> io::Result<()> {
+        let res = PathBuf::from(&self.output_directory).join(format!("{}.res", self.output_name));
+        let res = self.compile_to_res(&res)?;
+        let entries = parse_res(&fs::read(&res)?)?;
+        update_resources(pe_path.as_ref(), &entries)
+    }
+
+    /// Compile the resources to a `.res` file without linking them
+    ///
+    /// The resource file is compiled to `out` with the resource compiler of the target
+    /// toolkit, or of the host toolkit outside of a build script. No cargo directives
+    /// are printed, so the `.res` file can be passed to a custom link step or to tools
+    /// like `cvtres.exe`. Returns the path of the compiled file.
+    pub fn compile_to_res(&self, out: &Path) -> io::Result<PathBuf> {
+        self.validate()?;
+        self.check_resource_files()?;
+        let rc = self.resource_script()?;
+        let res = out.to_path_buf();
+        let target_env = env::var("CARGO_CFG_TARGET_ENV").unwrap_or_else(|_| {
+            if cfg!(target_env = "gnu") {
+                "gnu"
+            } else {
+                "msvc"
+            }
+            .to_string()
+        });
+        let (name, status, diagnostics) = if target_env == "gnu" {
+            // windres writes a .res file for the extension .res
+            let status = self.windres_command(Path::new(&rc), &res).output()?;
+            let diagnostics: Vec<Diagnostic> = String::from_utf8_lossy(&status.stderr)
+                .lines()
+                .filter_map(parse_windres_line)
+                .collect();
+            ("Windres", status, diagnostics)
+        } else if self.msvc_resource_compiler() == ResourceCompiler::LlvmRc {
+            let status = self.llvm_rc_command(Path::new(&rc), &res).output()?;
+            let diagnostics: Vec<Diagnostic> = String::from_utf8_lossy(&status.stderr)
+                .lines()
+                .filter_map(parse_windres_line)
+                .collect();
+            ("llvm-rc", status, diagnostics)
+        } else {
+            let status = self
+                .rc_command(&self.rc_exe()?, Path::new(&rc), &res)
+                .output()?;
+            let diagnostics: Vec<Diagnostic> = String::from_utf8_lossy(&status.stdout)
+                .lines()
+                .chain(String::from_utf8_lossy(&status.stderr).lines())
+                .filter_map(parse_rc_line)
+                .collect();
+            ("RC", status, diagnostics)
+        };
+        self.log_compiler_output(name, &status, &diagnostics);
+        if self.compiler_failed(&status, &diagnostics) {
+            return Err(WinresError::compiler(status, diagnostics).into());
+        }
+        self.apply_file_date(&res)?;
+        Ok(res)
+    }
+
+    fn compile_with_toolkit_msvc<'a>(
+        &self,
+        input: &'a str,
+        output_dir: &'a str,
+    ) -> Result<CompileOutput, WinresError> {
+        let rc_exe = self
+            .rc_exe()
+            .map_err(|e| WinresError::ToolkitNotFound(e.to_string()))?;
+        if self.toolkit_path.as_os_str().is_empty() && !rc_exe.is_file() {
+            let mut msg = "No Windows SDK found; set the toolkit path via set_toolkit_path() or install the Windows 10 SDK".to_string();
+            if let Some(e) = self.sdk_error.as_ref() {
+                msg.push_str(&format!("\n{}", e));
+            }
+            return Err(WinresError::ToolkitNotFound(msg));
+        }
+        self.log(
+            LogLevel::Normal,
+            &format!("Selected RC path: '{}'", rc_exe.display()),
+        );
+        let output = self.msvc_output(output_dir);
+        let input = PathBuf::from(input);
+        let status = run_toolkit(self.rc_command(&rc_exe, &input, &output))?;
+
+        // rc.exe reports errors on stdout
+        let diagnostics: Vec<Diagnostic> = String::from_utf8_lossy(&status.stdout)
+            .lines()
+            .chain(String::from_utf8_lossy(&status.stderr).lines())
+            .filter_map(parse_rc_line)
+            .collect();
+        self.log_compiler_output("RC", &status, &diagnostics);
+        if self.compiler_failed(&status, &diagnostics) {
+            return Err(WinresError::compiler(status, diagnostics));
+        }
+        self.apply_file_date(&output)?;
+
+        if self.keep_res && !self.prefer_native_res {
+            let res = PathBuf::from(output_dir).join(format!("{}.res", self.output_name));
+            fs::copy(&output, &res)?;
+        }
+
+        Ok(CompileOutput {
+            directives: self.link_directives(output_dir, "dylib", &self.output_name, &output),
+            artifact: Some(output),
+            diagnostics,
+            stdout: String::from_utf8_lossy(&status.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&status.stderr).into_owned(),
+        })
+    }
+
+    fn compile_with_toolkit_llvm<'a>(
+        &self,
+        input: &'a str,
+        output_dir: &'a str,
+    ) -> Result<CompileOutput, WinresError> {
+        self.log(
+            LogLevel::Normal,
+            &format!("Selected llvm-rc path: '{}'", self.llvm_rc_path),
+        );
+        let output = self.msvc_output(output_dir);
+        let input = PathBuf::from(input);
+        let status = run_toolkit(self.llvm_rc_command(&input, &output))?;
+
+        // llvm-rc preprocesses with clang, which reports errors like gcc
+        let diagnostics: Vec<Diagnostic> = String::from_utf8_lossy(&status.stderr)
+            .lines()
+            .filter_map(parse_windres_line)
+            .collect();
+        self.log_compiler_output("llvm-rc", &status, &diagnostics);
+        if self.compiler_failed(&status, &diagnostics) {
+            return Err(WinresError::compiler(status, diagnostics));
+        }
+        self.apply_file_date(&output)?;
+
+        if self.keep_res && !self.prefer_native_res {
+            let res = PathBuf::from(output_dir).join(format!("{}.res", self.output_name));
+            fs::copy(&output, &res)?;
+        }
+
+        Ok(CompileOutput {
+            directives: self.link_directives(output_dir, "dylib", &self.output_name, &output),
+            artifact: Some(output),
+            diagnostics,
+            stdout: String::from_utf8_lossy(&status.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&status.stderr).into_owned(),
+        })
+    }
+}
+
+/// The last numeric identifier of a pre-release, e.g., `4` for `beta.4`
+fn prerelease_number(pre: &str) -> u16 {
+    pre.rsplit('.').find_map(|id| id.parse().ok()).unwrap_or(0)
+}
+
+/// Run a program of the toolkit, a missing executable is reported as
+/// [`WinresError::ToolkitNotFound`]
+fn run_toolkit(mut command: process::Command) -> Result<process::Output, WinresError> {
+    command.output().map_err(|e| {
+        if e.kind() == io::ErrorKind::NotFound {
+            WinresError::ToolkitNotFound(format!(
+                "Could not run {}: {}",
+                command.get_program().to_string_lossy(),
+                e
+            ))
+        } else {
+            WinresError::Io(e)
+        }
+    })
+}
+
+/// The target triple for error messages, `fallback` outside of a build script
+fn target_name(fallback: &str) -> String {
+    env::var("TARGET").unwrap_or_else(|_| fallback.to_string())
+}
+
+/// Format a command for a shell, quoting arguments with spaces
+fn command_line(command: &process::Command) -> String {
+    let quote = |arg: &std::ffi::OsStr| {
+        let arg = arg.to_string_lossy();
+        if arg.is_empty() || arg.contains(' ') || arg.contains('\t') {
+            format!("\"{}\"", arg)
+        } else {
+            arg.into_owned()
+        }
+    };
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(quote)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The registry key with the installed Windows SDKs
+const SDK_REGISTRY_KEY: &str = r"HKLM\SOFTWARE\Microsoft\Windows Kits\Installed Roots";
+
+/// Find a Windows SDK
+fn get_sdk() -> io::Result<Vec<PathBuf>> {
+    // use the reg command, so we don't need a winapi dependency
+    let output = process::Command::new("reg")
+        .arg("query")
+        .arg(SDK_REGISTRY_KEY)
+        .arg("/reg:32")
+        .output()
+        .map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("Could not run reg to query {}: {}", SDK_REGISTRY_KEY, e),
+            )
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let kind = if stderr.contains("Access is denied") {
+            io::ErrorKind::PermissionDenied
+        } else {
+            io::ErrorKind::Other
+        };
+        return Err(io::Error::new(
+            kind,
+            format!(
+                "Querying the registry key {} failed with error message:\n{}",
+                SDK_REGISTRY_KEY,
+                stderr.trim_end()
+            ),
+        ));
+    }
+
+    let lines = String::from_utf8(output.stdout)
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+    let mut kits: Vec<PathBuf> = Vec::new();
+    let mut lines: Vec<&str> = lines.lines().collect();
+    lines.reverse();
+    for line in lines {
+        if line.trim().starts_with("KitsRoot") {
+            let kit: String = line
+                .chars()
+                .skip(line.find("REG_SZ").unwrap() + 6)
+                .skip_while(|c| c.is_whitespace())
+                .collect();
+
+            let p = PathBuf::from(&kit);
+            if let Some(rc) = sdk_archs()
+                .iter()
+                .map(|arch| p.join("bin").join(arch).join("rc.exe"))
+                .find(|rc| rc.exists())
+            {
+                kits.push(rc.parent().unwrap().to_owned());
+            }
+
+            if let Ok(bin) = p.join("bin").read_dir() {
+                for e in bin.filter_map(|e| e.ok()) {
+                    if let Some(rc) = sdk_archs()
+                        .iter()
+                        .map(|arch| e.path().join(arch).join("rc.exe"))
+                        .find(|rc| rc.exists())
+                    {
+                        kits.push(rc.parent().unwrap().to_owned());
+                    }
+                }
+            }
+        }
+    }
+    if kits.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "Can not find rc.exe in the Windows SDKs listed in {}",
+                SDK_REGISTRY_KEY
+            ),
+        ));
+    }
+
+    Ok(kits)
+}
+
+/// The `bin` directory of the SDK with the given version, see [`Sdk::detect_version()`]
+///
+/// [`Sdk::detect_version()`]: struct.Sdk.html#method.detect_version
+fn select_sdk_version(kits: Vec<PathBuf>, version: &str) -> io::Result<PathBuf> {
+    let mut found = Vec::new();
+    for kit in kits {
+        match Sdk::from_path(&kit).version() {
+            Some(v) if v == version => return Ok(kit),
+            Some(v) => found.push(v.to_string()),
+            None => {}
+        }
+    }
+    found.sort();
+    found.dedup();
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!(
+            "Windows SDK {} is not installed, found: {}",
+            version,
+            if found.is_empty() {
+                "none".to_string()
+            } else {
+                found.join(", ")
+            }
+        ),
+    ))
+}
+
+/// The directory of the Windows SDK `bin` directory for an architecture, e.g.,
+/// `x64` for `x86_64`
+fn sdk_arch(target_arch: &str) -> Option<&'static str> {
+    match target_arch {
+        "x86_64" => Some("x64"),
+        "x86" => Some("x86"),
+        "aarch64" | "arm64ec" => Some("arm64"),
+        "arm" => Some("arm"),
+        _ => None,
+    }
+}
+
+/// The SDK `bin` directories to search for `rc.exe`
+///
+/// The directory of the target architecture (`CARGO_CFG_TARGET_ARCH`) comes first,
+/// followed by the one of the host. The compiled resource does not depend on the
+/// architecture of `rc.exe`, so the host version works if the SDK lacks the other.
+fn sdk_archs() -> Vec<&'static str> {
+    sdk_archs_for(&env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default())
+}
+
+/// The SDK `bin` directories to search for `rc.exe` for a target architecture
+fn sdk_archs_for(target_arch: &str) -> Vec<&'static str> {
+    let host = if cfg!(target_arch = "x86_64") {
+        "x64"
+    } else if cfg!(target_arch = "aarch64") {
+        "arm64"
+    } else {
+        "x86"
+    };
+    let mut archs: Vec<&'static str> = sdk_arch(target_arch).into_iter().collect();
+    if !archs.contains(&host) {
+        archs.push(host);
+    }
+    archs
+}
+
+/// Locate `rc.exe` like `cc` locates the MSVC tools, i.e., in the Visual Studio
+/// environment (`VCINSTALLDIR`) or installation, and return its directory
+#[cfg(feature = "cc")]
+fn find_rc_with_cc() -> Option<PathBuf> {
+    let target = env::var("TARGET").unwrap_or_else(|_| env::consts::ARCH.to_string());
+    let tool = cc::windows_registry::find_tool(&target, "rc.exe")?;
+    if tool.path().is_file() {
+        tool.path().parent().map(Path::to_path_buf)
+    } else {
+        None
+    }
+}
+
+#[cfg(not(feature = "cc"))]
+fn find_rc_with_cc() -> Option<PathBuf> {
+    None
+}
+
+/// Search the directories in `%PATH%` for an executable
+fn find_in_path(name: &str) -> Option<PathBuf> {
+    let paths = env::var_os("PATH")?;
+    env::split_paths(&paths)
+        .flat_map(|dir| {
+            let exe = dir.join(name);
+            let with_ext = dir.join(format!("{}{}", name, env::consts::EXE_SUFFIX));
+            vec![exe, with_ext]
+        })
+        .find(|p| p.is_file())
+}
+
+/// Make a relative path absolute with the current directory
+///
+/// Unlike `fs::canonicalize()` this does not require the path to exist and does not
+/// return a `\\?\` path on Windows, which the MinGW tools do not understand.
+fn absolute_path(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+    match env::current_dir() {
+        Ok(dir) => dir.join(path),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// The names to try for a MinGW tool, e.g., `x86_64-w64-mingw32-windres`, `windres`
+/// and `llvm-windres` for the target architecture `x86_64`
+#[cfg(any(unix, test))]
+fn gnu_tool_candidates(tool: &str, target_arch: &str) -> Vec<String> {
+    let triple = match target_arch {
+        "x86_64" => Some("x86_64-w64-mingw32"),
+        "x86" => Some("i686-w64-mingw32"),
+        "aarch64" => Some("aarch64-w64-mingw32"),
+        _ => None,
+    };
+    triple
+        .map(|triple| format!("{}-{}", triple, tool))
+        .into_iter()
+        .chain(vec![tool.to_string(), format!("llvm-{}", tool)])
+        .collect()
+}
+
+/// Find a MinGW tool for the target in `PATH`, like `cc` finds `<triple>-gcc`
+///
+/// Falls back to the plain name, so the error names the expected tool.
+#[cfg(unix)]
+fn find_gnu_tool(tool: &str) -> String {
+    let target_arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default();
+    gnu_tool_candidates(tool, &target_arch)
+        .into_iter()
+        .find(|name| find_in_path(name).is_some())
+        .unwrap_or_else(|| tool.to_string())
+}
+
+/// The MinGW include directories belonging to a `windres` executable
+fn mingw_include_dirs(windres: &str, toolkit_path: &Path) -> Vec<PathBuf> {
+    let windres = Path::new(windres);
+    let bin = match windres.parent() {
+        Some(p) if !p.as_os_str().is_empty() => Some(p.to_path_buf()),
+        _ if toolkit_path.join(windres).is_file() => Some(toolkit_path.to_path_buf()),
+        _ => {
+            find_in_path(&windres.to_string_lossy()).and_then(|p| p.parent().map(Path::to_path_buf))
+        }
+    };
+    let bin = match bin {
+        Some(bin) => bin,
+        None => return Vec::new(),
+    };
+
+    let mut candidates = vec![bin.join("..").join("include")];
+    // cross compilers are prefixed with the target triple, e.g., x86_64-w64-mingw32-windres
+    let name = windres.file_stem().unwrap_or_default().to_string_lossy();
+    if let Some(triple) = name.strip_suffix("-windres") {
+        candidates.push(bin.join("..").join(triple).join("include"));
+    }
+    candidates.into_iter().filter(|p| p.is_dir()).collect()
+}
+
+/// An environment variable that cargo sets for build scripts
+fn cargo_env(name: &str) -> Result<String, WinresError> {
+    env::var(name).map_err(|_| WinresError::MissingEnvVar(name.to_string()))
+}
+
+/// Read the properties from `Cargo.toml`, invalid metadata is returned in `errors`
+fn parse_cargo_toml(
+    props: &mut HashMap<String, String>,
+    ver: &mut HashMap<VersionInfo, u64>,
+    log: &mut Vec<(LogLevel, String)>,
+    errors: &mut Vec<String>,
+) -> Result<(), WinresError> {
+    let cargo = Path::new(&cargo_env("CARGO_MANIFEST_DIR")?).join("Cargo.toml");
+    let mut f = fs::File::open(cargo)?;
+    let mut cargo_toml = String::new();
+    f.read_to_string(&mut cargo_toml)?;
+    match cargo_toml.parse::<toml::Value>() {
+        Ok(ml) => read_winres_metadata(&ml, props, ver, log, errors),
+        Err(e) => errors.push(format!("TOML parsing error: {}", e)),
+    }
+    Ok(())
+}
+
+/// Read the string properties and version info fields in `package.metadata.winres`
+///
+/// Missing sections are only logged, values that cannot be used are returned in
+/// `errors`.
+fn read_winres_metadata(
+    ml: &toml::Value,
+    props: &mut HashMap<String, String>,
+    ver: &mut HashMap<VersionInfo, u64>,
+    log: &mut Vec<(LogLevel, String)>,
+    errors: &mut Vec<String>,
+) {
+    let mut note = |msg: &str| log.push((LogLevel::Verbose, msg.to_string()));
+    if let Some(pkg) = ml.get("package") {
+        if let Some(pkg) = pkg.get("metadata") {
+            if let Some(pkg) = pkg.get("winres") {
+                if let Some(pkg) = pkg.as_table() {
+                    for (k, v) in pkg {
+                        if let Some(field) = version_info_field(k) {
+                            match version_info_value(&field, v) {
+                                Ok(value) => {
+                                    ver.insert(field, value);
+                                }
+                                Err(e) => {
+                                    errors.push(format!("package.metadata.winres.{} {}", k, e))
+                                }
+                            }
+                        } else if let Some(v) = v.as_str() {
+                            props.insert(k.clone(), v.to_string());
+                        } else {
+                            errors.push(format!("package.metadata.winres.{} is not a string", k));
+                        }
+                    }
+                } else {
+                    errors.push("package.metadata.winres is not a table".to_string());
+                }
+            } else {
+                note("package.metadata.winres does not exist");
+            }
+        } else {
+            note("package.metadata does not exist");
+        }
+    } else {
+        note("package does not exist");
+    }
+}
+
+/// The version info field for a key in `package.metadata.winres`, e.g., `FILEFLAGS`
+fn version_info_field(key: &str) -> Option<VersionInfo> {
+    match key {
+        "FILEVERSION" => Some(VersionInfo::FILEVERSION),
+        "PRODUCTVERSION" => Some(VersionInfo::PRODUCTVERSION),
+        "FILEOS" => Some(VersionInfo::FILEOS),
+        "FILETYPE" => Some(VersionInfo::FILETYPE),
+        "FILESUBTYPE" => Some(VersionInfo::FILESUBTYPE),
+        "FILEFLAGSMASK" => Some(VersionInfo::FILEFLAGSMASK),
+        "FILEFLAGS" => Some(VersionInfo::FILEFLAGS),
+        _ => None,
+    }
+}
+
+/// The value of a version info field in `package.metadata.winres`, an integer or,
+/// for the versions, a string like `"1.2.3.4"`
+fn version_info_value(field: &VersionInfo, value: &toml::Value) -> Result<u64, String> {
+    match (field, value) {
+        (_, toml::Value::Integer(i)) if *i >= 0 => Ok(*i as u64),
+        (VersionInfo::FILEVERSION, toml::Value::String(s))
+        | (VersionInfo::PRODUCTVERSION, toml::Value::String(s)) => parse_version(s),
+        (VersionInfo::FILEVERSION, _) | (VersionInfo::PRODUCTVERSION, _) => {
+            Err("is not a version like \"1.2.3.4\" or an integer".to_string())
+        }
+        _ => Err("is not a positive integer".to_string()),
+    }
+}
+
+/// Parse a version with up to four numbers, e.g., `"1.2.3.4"`, into the packed value
+///
+/// Missing numbers are `0`, e.g., `"1.2"` is `1.2.0.0`.
+fn parse_version(version: &str) -> Result<u64, String> {
+    let parts: Vec<&str> = version.split('.').collect();
+    if parts.len() > 4 {
+        return Err(format!("{:?} has more than four numbers", version));
+    }
+    let mut words = [0u16; 4];
+    for (word, part) in words.iter_mut().zip(parts) {
+        *word = part.parse().map_err(|_| {
+            format!(
+                "{:?} is not a version like \"1.2.3.4\" with numbers up to 65535",
+                version
+            )
+        })?;
+    }
+    Ok(pack_version(words[0], words[1], words[2], words[3]))
+}
+
+/// Parse a line of `rc.exe` output, e.g.,
+/// `C:\res\resource.rc(12) : error RC2135 : file not found: icon.ico`
+fn parse_rc_line(line: &str) -> Option<Diagnostic> {
+    let line = line.trim();
+    let (location, rest) = match line.find(" : ") {
+        Some(pos) if line[..pos].ends_with(')') && line[..pos].contains('(') => {
+            (Some(&line[..pos]), &line[pos + 3..])
+        }
+        _ => (None, line),
+    };
+    let (severity, rest) = if let Some(rest) = rest.strip_prefix("fatal error ") {
+        (Severity::Error, rest)
+    } else if let Some(rest) = rest.strip_prefix("error ") {
+        (Severity::Error, rest)
+    } else if let Some(rest) = rest.strip_prefix("warning ") {
+        (Severity::Warning, rest)
+    } else if let Some(rest) = rest.strip_prefix("note ") {
+        (Severity::Note, rest)
+    } else {
+        return None;
+    };
+    // skip the message code, e.g., RC2135
+    let message = match rest.find(':') {
+        Some(pos) => rest[pos + 1..].trim(),
+        None => rest.trim(),
+    };
+
+    let (file, line) = match location {
+        Some(location) => {
+            let open = location.rfind('(').unwrap();
+            (
+                Some(location[..open].to_string()),
+                location[open + 1..location.len() - 1].trim().parse().ok(),
+            )
+        }
+        None => (None, None),
+    };
+    Some(Diagnostic {
+        severity,
+        message: message.to_string(),
+        file,
+        line,
+    })
+}
+
+/// Parse a line of `windres` (or its preprocessor's) output, e.g.,
+/// `resource.rc:3: syntax error` or `resource.rc:1:10: fatal error: foo.h: No such file`
+fn parse_windres_line(line: &str) -> Option<Diagnostic> {
+    // continuation lines of the preprocessor start with whitespace
+    if line.trim().is_empty() || line.starts_with(char::is_whitespace) {
+        return None;
+    }
+    // windres prefixes its own messages with the program name
+    let line = match line.find("windres: ") {
+        Some(pos) if !line[..pos].contains(' ') => &line[pos + 9..],
+        _ => line,
+    };
+    if line.starts_with("In file included from") || line.starts_with("from ") {
+        return None;
+    }
+
+    // find the first `:<line>:`, a file name may contain a drive letter
+    let mut location = None;
+    for (pos, _) in line.match_indices(':') {
+        let digits: String = line[pos + 1..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        if !digits.is_empty() && line[pos + 1 + digits.len()..].starts_with(':') {
+            location = Some((pos, digits));
+            break;
+        }
+    }
+
+    let (file, number, mut rest) = match location {
+        Some((pos, digits)) => {
+            let mut rest = &line[pos + 2 + digits.len()..];
+            // skip the column
+            let column = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+            if column > 0 && rest[column..].starts_with(':') {
+                rest = &rest[column + 1..];
+            }
+            (
+                Some(line[..pos].to_string()),
+                digits.parse().ok(),
+                rest.trim(),
+            )
+        }
+        None => (None, None, line.trim()),
+    };
+
+    let mut severity = Severity::Error;
+    for (prefix, s) in [
+        ("fatal error:", Severity::Error),
+        ("error:", Severity::Error),
+        ("warning:", Severity::Warning),
+        ("note:", Severity::Note),
+    ]
+    .iter()
+    {
+        if let Some(r) = rest.strip_prefix(prefix) {
+            severity = *s;
+            rest = r.trim();
+            break;
+        }
+    }
+    Some(Diagnostic {
+        severity,
+        message: rest.to_string(),
+        file,
+        line: number,
+    })
+}
+
+/// Sort key for a name ID in the resource directory: string names come first in
+/// case-insensitive order, then integer IDs in ascending order
+fn resource_directory_key(name_id: &str) -> (bool, u16, String) {
+    match name_id.parse::<u16>() {
+        Ok(id) => (true, id, String::new()),
+        Err(_) => (false, 0, name_id.to_uppercase()),
+    }
+}
+
+/// The LANGID of a language tag, see [`WindowsResource::set_language_str()`]
+///
+/// [`WindowsResource::set_language_str()`]: struct.WindowsResource.html#method.set_language_str
+fn language_id(tag: &str) -> Option<u16> {
+    let tag = tag.replace('_', "-");
+    if tag.eq_ignore_ascii_case("und") {
+        return Some(0);
+    }
+    if let Some(&(_, id)) = LANGUAGE_TAGS
+        .iter()
+        .find(|(t, _)| t.eq_ignore_ascii_case(&tag))
+    {
+        return Some(id);
+    }
+    if tag.contains('-') {
+        return None;
+    }
+    LANGUAGE_TAGS
+        .iter()
+        .find(|(t, _)| t.split('-').next().unwrap().eq_ignore_ascii_case(&tag))
+        .map(|&(_, id)| id & 0x3ff)
+}
+
+/// Icon sizes Windows uses without scaling
+const STANDARD_ICON_SIZES: &[u32] = &[16, 20, 24, 32, 40, 48, 64, 96, 128, 256];
+
+/// A description of the file format for error messages, guessed from the first bytes
+fn file_format(header: &[u8]) -> &'static str {
+    if header.starts_with(&[0, 0, 1, 0]) {
+        "an ICO icon"
+    } else if header.starts_with(&[0, 0, 2, 0]) {
+        "a CUR cursor"
+    } else if header.starts_with(b"\x89PNG") {
+        "a PNG image"
+    } else if header.starts_with(b"BM") {
+        "a BMP image"
+    } else if header.starts_with(&[0xff, 0xd8, 0xff]) {
+        "a JPEG image"
+    } else if header.starts_with(b"GIF8") {
+        "a GIF image"
+    } else {
+        "an unknown format"
+    }
+}
+
+/// The image sizes `(width, height)` in the directory of an `.ico` file
+fn ico_sizes(data: &[u8]) -> io::Result<Vec<(u32, u32)>> {
+    if data.len() < 6 || data[0..4] != [0, 0, 1, 0] {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not an ICO file",
+        ));
+    }
+    let count = usize::from(data[4]) | usize::from(data[5]) << 8;
+    if data.len() < 6 + count * 16 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "ICO directory is truncated",
+        ));
+    }
+    // a size of 0 means 256 pixels
+    let size = |b: u8| if b == 0 { 256 } else { u32::from(b) };
+    Ok((0..count)
+        .map(|i| {
+            let entry = &data[6 + i * 16..];
+            (size(entry[0]), size(entry[1]))
+        })
+        .collect())
+}
+
+/// The type or name of a resource in a `.res` file
+#[derive(Debug, PartialEq)]
+enum ResName {
+    Id(u16),
+    /// UTF-16, including the terminating NUL
+    Name(Vec<u16>),
+}
+
+/// A resource in a compiled `.res` file
+#[derive(Debug, PartialEq)]
+#[cfg_attr(not(windows), allow(dead_code))]
+struct ResEntry {
+    type_id: ResName,
+    name_id: ResName,
+    language: u16,
+    data: Vec<u8>,
+}
+
+/// Split a `.res` file into its resources
+///
+/// Every resource has a header with its size, type, name and language, followed by
+/// the data. Headers and data are aligned to 4 bytes. The file starts with an empty
+/// resource of type `0`, which is skipped.
+fn parse_res(data: &[u8]) -> io::Result<Vec<ResEntry>> {
+    let truncated = || io::Error::new(io::ErrorKind::InvalidData, "truncated .res file");
+    let u16_at = |i: usize| {
+        data.get(i..i + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .ok_or_else(truncated)
+    };
+    let u32_at = |i: usize| {
+        data.get(i..i + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or_else(truncated)
+    };
+    let name_at = |mut i: usize| -> io::Result<(ResName, usize)> {
+        if u16_at(i)? == 0xffff {
+            return Ok((ResName::Id(u16_at(i + 2)?), i + 4));
+        }
+        let mut name = Vec::new();
+        loop {
+            let c = u16_at(i)?;
+            name.push(c);
+            i += 2;
+            if c == 0 {
+                return Ok((ResName::Name(name), i));
+            }
+        }
+    };
+    let align = |i: usize| (i + 3) & !3;
+
+    let mut entries = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let data_size = u32_at(pos)? as usize;
+        let header_size = u32_at(pos + 4)? as usize;
+        let (type_id, next) = name_at(pos + 8)?;
+        let (name_id, next) = name_at(next)?;
+        // DataVersion, MemoryFlags, LanguageId
+        let language = u16_at(align(next) + 6)?;
+        let start = pos + header_size;
+        let content = data.get(start..start + data_size).ok_or_else(truncated)?;
+        if type_id != ResName::Id(0) {
+            entries.push(ResEntry {
+                type_id,
+                name_id,
+                language,
+                data: content.to_vec(),
+            });
+        }
+        pos = align(start + data_size);
+    }
+    Ok(entries)
+}
+
+/// Set `dwFileDateMS` and `dwFileDateLS` of the version resource in a compiled
+/// resource, returns whether it was found
+///
+/// The `VS_FIXEDFILEINFO` block follows the UTF-16 key `VS_VERSION_INFO`, which is
+/// padded to 4 bytes. The layout is the same in `.res` files and in the `.rsrc`
+/// section of the object files written by `windres`.
+fn patch_file_date(data: &mut [u8], date: u64) -> bool {
+    let mut pattern: Vec<u8> = "VS_VERSION_INFO\0"
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    // padding and the signature 0xFEEF04BD
+    pattern.extend_from_slice(&[0, 0, 0xbd, 0x04, 0xef, 0xfe]);
+    let mut found = false;
+    let mut pos = 0;
+    while let Some(offset) = data[pos..]
+        .windows(pattern.len())
+        .position(|w| w == &pattern[..])
+    {
+        let fixed = pos + offset + pattern.len() - 4;
+        // the date follows signature, struct version, versions, flags, OS and types
+        if let Some(field) = data.get_mut(fixed + 44..fixed + 52) {
+            field[..4].copy_from_slice(&((date >> 32) as u32).to_le_bytes());
+            field[4..].copy_from_slice(&(date as u32).to_le_bytes());
+            found = true;
+        }
+        pos = fixed;
+    }
+    found
+}
+
+/// Write resources into an executable or DLL
+#[cfg(windows)]
+fn update_resources(pe: &Path, entries: &[ResEntry]) -> io::Result<()> {
+    use std::os::raw::c_void;
+    use std::os::windows::ffi::OsStrExt;
+
+    // kernel32 is always linked, so we don't need a winapi dependency
+    extern "system" {
+        fn BeginUpdateResourceW(file_name: *const u16, delete_existing: i32) -> *mut c_void;
+        fn UpdateResourceW(
+            update: *mut c_void,
+            type_id: *const u16,
+            name_id: *const u16,
+            language: u16,
+            data: *const c_void,
+            size: u32,
+        ) -> i32;
+        fn EndUpdateResourceW(update: *mut c_void, discard: i32) -> i32;
+    }
+
+    // MAKEINTRESOURCE for numeric IDs
+    let ptr = |name: &ResName| match name {
+        ResName::Id(id) => usize::from(*id) as *const u16,
+        ResName::Name(name) => name.as_ptr(),
+    };
+    let file: Vec<u16> = pe.as_os_str().encode_wide().chain(Some(0)).collect();
+    unsafe {
+        let update = BeginUpdateResourceW(file.as_ptr(), 0);
+        if update.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        for entry in entries {
+            let ok = UpdateResourceW(
+                update,
+                ptr(&entry.type_id),
+                ptr(&entry.name_id),
+                entry.language,
+                entry.data.as_ptr() as *const c_void,
+                entry.data.len() as u32,
+            );
+            if ok == 0 {
+                let err = io::Error::last_os_error();
+                EndUpdateResourceW(update, 1);
+                return Err(err);
+            }
+        }
+        if EndUpdateResourceW(update, 0) == 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn update_resources(_pe: &Path, _entries: &[ResEntry]) -> io::Result<()> {
+    Err(io::Error::other(
+        "Resources can only be embedded into a binary on a Windows host",
+    ))
+}
+
+/// 64 bit FNV-1a hash, stable across Rust versions and platforms
+fn content_hash(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(feature = "conversion")]
+const PNG_MAGIC: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Read a PNG or BMP image for an `.ico` file
+#[cfg(feature = "conversion")]
+fn icon_image(data: Vec<u8>) -> io::Result<IcoImage> {
+    if data.starts_with(PNG_MAGIC) {
+        IcoImage::from_png(data)
+    } else if data.starts_with(b"BM") {
+        IcoImage::from_bmp(&data)
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not a PNG or BMP image",
+        ))
+    }
+}
+
+/// Split an `.ico` file into its images
+#[cfg(feature = "conversion")]
+fn read_ico(data: &[u8]) -> io::Result<Vec<IcoImage>> {
+    let sizes = ico_sizes(data)?;
+    let u16_at = |i: usize| u16::from_le_bytes([data[i], data[i + 1]]);
+    let u32_at = |i: usize| u32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]);
+    sizes
+        .into_iter()
+        .enumerate()
+        .map(|(i, (width, height))| {
+            let entry = 6 + i * 16;
+            let len = u32_at(entry + 8) as usize;
+            let offset = u32_at(entry + 12) as usize;
+            let image = data.get(offset..offset + len).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "ICO image is truncated")
+            })?;
+            Ok(IcoImage {
+                width,
+                height,
+                color_count: data[entry + 2],
+                planes: u16_at(entry + 4),
+                bit_count: u16_at(entry + 6),
+                data: image.to_vec(),
+            })
+        })
+        .collect()
+}
+
+/// A single image of an `.ico` file
+#[cfg(feature = "conversion")]
+#[derive(Debug)]
+struct IcoImage {
+    width: u32,
+    height: u32,
+    color_count: u8,
+    planes: u16,
+    bit_count: u16,
+    data: Vec<u8>,
+}
+
+#[cfg(feature = "conversion")]
+impl IcoImage {
+    /// PNG images are stored unmodified, we only need the header
+    fn from_png(data: Vec<u8>) -> io::Result<IcoImage> {
+        if data.len() < 33 || &data[12..16] != b"IHDR" {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "PNG header is missing",
+            ));
+        }
+        let be32 = |o: usize| {
+            u32::from(data[o]) << 24
+                | u32::from(data[o + 1]) << 16
+                | u32::from(data[o + 2]) << 8
+                | u32::from(data[o + 3])
+        };
+        let depth = u16::from(data[24]);
+        let channels = match data[25] {
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            _ => 1,
+        };
+        Ok(IcoImage {
+            width: be32(16),
+            height: be32(20),
+            color_count: 0,
+            planes: 1,
+            bit_count: depth * channels,
+            data,
+        })
+    }
+
+    /// BMP images are stored as DIB without the file header, with doubled height
+    /// and a transparency mask
+    fn from_bmp(data: &[u8]) -> io::Result<IcoImage> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+        if data.len() < 54 {
+            return Err(invalid("BMP header is missing"));
+        }
+        let le16 = |o: usize| u16::from(data[o]) | u16::from(data[o + 1]) << 8;
+        let le32 = |o: usize| u32::from(le16(o)) | u32::from(le16(o + 2)) << 16;
+        let offset = le32(10) as usize;
+        let header_size = le32(14) as usize;
+        let width = le32(18);
+        let height = le32(22) as i32;
+        let bit_count = le16(28);
+        if header_size < 40 || le32(30) != 0 {
+            return Err(invalid("only uncompressed BMP images are supported"));
+        }
+        if height <= 0 {
+            return Err(invalid("top-down BMP images are not supported"));
+        }
+        if ![1, 4, 8, 16, 24, 32].contains(&bit_count) {
+            return Err(invalid("unsupported BMP bit count"));
+        }
+        let height = height as u32;
+        let colors = match le32(46) {
+            0 if bit_count <= 8 => 1 << bit_count,
+            n => n as usize,
+        };
+        // the sizes come from the header, which might be hostile
+        let too_large = || invalid("BMP image is too large");
+        let palette = header_size.checked_add(14).ok_or_else(too_large)?;
+        let stride = (width as usize)
+            .checked_mul(bit_count as usize)
+            .ok_or_else(too_large)?
+            .div_ceil(32)
+            * 4;
+        let pixels = stride.checked_mul(height as usize).ok_or_else(too_large)?;
+        let palette_end = colors
+            .checked_mul(4)
+            .and_then(|size| size.checked_add(palette))
+            .ok_or_else(too_large)?;
+        let pixels_end = offset.checked_add(pixels).ok_or_else(too_large)?;
+        if data.len() < palette_end || data.len() < pixels_end {
+            return Err(invalid("BMP image is truncated"));
+        }
+        let mask_height = height.checked_mul(2).ok_or_else(too_large)?;
+        // at most as large as the pixels with at least 1 bit per pixel
+        let mask_size = (width as usize).div_ceil(32) * 4 * height as usize;
+
+        let mut dib = data[14..54].to_vec();
+        // BITMAPINFOHEADER with XOR and AND mask height
+        dib[0..4].copy_from_slice(&40u32.to_le_bytes());
+        dib[8..12].copy_from_slice(&mask_height.to_le_bytes());
+        dib[20..24].copy_from_slice(&0u32.to_le_bytes());
+        dib.extend_from_slice(&data[palette..palette_end]);
+        dib.extend_from_slice(&data[offset..pixels_end]);
+        // opaque AND mask, 32 bit images use their alpha channel
+        dib.resize(dib.len() + mask_size, 0);
+
+        Ok(IcoImage {
+            width,
+            height,
+            color_count: if colors < 256 { colors as u8 } else { 0 },
+            planes: 1,
+            bit_count,
+            data: dib,
+        })
+    }
+}
+
+/// Serialize images into an `.ico` file
+#[cfg(feature = "conversion")]
+fn write_ico(images: &[IcoImage]) -> Vec<u8> {
+    let mut ico = Vec::new();
+    ico.extend_from_slice(&[0, 0, 1, 0]);
+    ico.extend_from_slice(&(images.len() as u16).to_le_bytes());
+    let mut offset = 6 + 16 * images.len() as u32;
+    for image in images {
+        // 256 pixels are stored as 0
+        ico.push(image.width as u8);
+        ico.push(image.height as u8);
+        ico.push(image.color_count);
+        ico.push(0);
+        ico.extend_from_slice(&image.planes.to_le_bytes());
+        ico.extend_from_slice(&image.bit_count.to_le_bytes());
+        ico.extend_from_slice(&(image.data.len() as u32).to_le_bytes());
+        ico.extend_from_slice(&offset.to_le_bytes());
+        offset += image.data.len() as u32;
+    }
+    for image in images {
+        ico.extend_from_slice(&image.data);
+    }
+    ico
+}
+
+fn escape_string(string: &str) -> String {
+    let mut escaped = String::new();
+    for chr in string.chars() {
+        // In quoted RC strings, double-quotes are escaped by using two
+        // consecutive double-quotes.  Other characters are escaped in the
+        // usual C way using backslashes.
+        match chr {
+            '"' => escaped.push_str("\"\""),
+            '\'' => escaped.push_str("\\'"),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            // rc.exe reads up to three octal digits, so always write three to not
+            // swallow a following digit
+            '\0'..='\x1f' | '\x7f' => escaped.push_str(&format!("\\{:03o}", chr as u32)),
+            _ => escaped.push(chr),
+        };
+    }
+    escaped
+}
+
+/// Like [`escape_string()`], but characters outside the Basic Multilingual Plane are
+/// written as `\xHHHH` escapes of their UTF-16 surrogate pair, for an `L"..."` string
+///
+/// Some versions of `rc.exe` cannot read four byte UTF-8 sequences. `windres` reads
+/// the hex digits of `\x` greedily, so a hex digit following an escape is escaped too.
+///
+/// [`escape_string()`]: fn.escape_string.html
+fn escape_wide_string(string: &str) -> String {
+    let mut escaped = String::new();
+    let mut after_escape = false;
+    for chr in string.chars() {
+        if chr > '\u{ffff}' || (after_escape && chr.is_ascii_hexdigit()) {
+            let mut units = [0; 2];
+            for unit in chr.encode_utf16(&mut units) {
+                escaped.push_str(&format!("\\x{:04x}", unit));
+            }
+            after_escape = true;
+        } else {
+            after_escape = false;
+            escaped.push_str(&escape_string(chr.encode_utf8(&mut [0; 4])));
+        }
+    }
+    escaped
+}
+
+fn win_sdk_inlcude_root(path: &Path) -> PathBuf {
+    let mut tools_path = PathBuf::new();
+    let mut iter = path.iter();
+    while let Some(p) = iter.next() {
+        if p == "bin" {
+            let version = iter.next().unwrap();
+            tools_path.push("Include");
+            if version.to_string_lossy().starts_with("10.") {
+                tools_path.push(version);
+            }
+            break;
+        } else {
+            tools_path.push(p);
+        }
+    }
+
+    tools_path
+}
+
+/// The SDK include directories that have to be searched so `<windows.h>` and
+/// `<winresrc.h>` resolve for the given `rc.exe`
+fn win_sdk_include_dirs(rc_exe: &Path) -> Vec<PathBuf> {
+    let mut root = win_sdk_inlcude_root(rc_exe);
+    // rc.exe was found in an unversioned bin directory, so pick the newest
+    // versioned include directory of a Windows 10 SDK
+    if !root.join("um").exists() {
+        if let Ok(entries) = root.read_dir() {
+            let mut versions: Vec<PathBuf> = entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.join("um").is_dir())
+                .collect();
+            versions.sort_by_key(|p| {
+                p.file_name()
+                    .and_then(|n| n.to_str())
+                    .map(|n| n.split('.').map(|c| c.parse().unwrap_or(0)).collect())
+                    .unwrap_or_else(Vec::<u32>::new)
+            });
+            if let Some(newest) = versions.pop() {
+                root = newest;
+            }
+        }
+    }
+
+    // SDKs before Windows 8 have a flat include directory
+    if !root.join("um").exists() && root.join("WinResrc.h").exists() {
+        return vec![root];
+    }
+
+    let mut dirs = vec![root.join("um"), root.join("shared")];
+    if root.join("ucrt").exists() {
+        dirs.push(root.join("ucrt"));
+    }
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::escape_string;
+    use super::win_sdk_inlcude_root;
+    use super::{pack_version, unpack_version};
+    use super::{parse_rc_line, parse_windres_line, Severity};
+    use super::{VersionInfo, WindowsResource};
+    use std::path::{Path, PathBuf};
+
+    /// A resource with fixed values, independent of the crate's own `Cargo.toml`
+    fn resource() -> WindowsResource {
+        let mut res = WindowsResource::new();
+        res.properties.clear();
+        res.set("ProductName", "Golden")
+            .set("FileDescription", "Golden \"test\" ⛄")
+            .set("FileVersion", "1.2.3")
+            .set("ProductVersion", "1.2.3")
+            .set_version_info(VersionInfo::FILEVERSION, pack_version(1, 2, 3, 0))
+            .set_version_info(VersionInfo::PRODUCTVERSION, pack_version(1, 2, 3, 0));
+        res
+    }
+
+    /// Compare the generated resource file with `tests/golden/<name>.rc`
+    ///
+    /// The crate's root directory is replaced by `$CARGO_MANIFEST_DIR/`.
+    /// Run the tests with `WINRES_BLESS=1` to update the golden files.
+    fn assert_golden(name: &str, res: &WindowsResource) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("golden")
+            .join(format!("{}.rc", name));
+        let root = format!(
+            "{}{}",
+            env!("CARGO_MANIFEST_DIR"),
+            std::path::MAIN_SEPARATOR
+        );
+        let rc = res
+            .generate_resource_string()
+            .unwrap()
+            .replace(&escape_string(&root), "$CARGO_MANIFEST_DIR/");
+        if std::env::var_os("WINRES_BLESS").is_some() {
+            std::fs::write(&path, &rc).unwrap();
+        }
+        let golden = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            rc,
+            golden,
+            "{} differs from the generated resource",
+            path.display()
+        );
+    }
+
+    #[test]
+    fn golden_basic() {
+        assert_golden("basic", &resource());
+    }
+
+    #[test]
+    fn golden_icons() {
+        let mut res = resource();
+        res.set_icon("icon.ico")
+            .set_icon_with_id("icons/second.ico", "2")
+            .set_language(0x0409);
+        assert_golden("icons", &res);
+    }
+
+    #[test]
+    fn golden_images() {
+        let mut res = resource();
+        res.set_icon("icon.ico")
+            .add_bitmap("TOOLBAR", "images/toolbar.bmp")
+            .add_bitmap("101", "splash.bmp")
+            .add_cursor("CROSSHAIR", "cursors/crosshair.cur");
+        assert_golden("images", &res);
+    }
+
+    #[test]
+    fn golden_accelerators() {
+        use super::{AcceleratorEntry, AcceleratorKey};
+        let entry = |key, command| AcceleratorEntry {
+            key,
+            command,
+            control: false,
+            shift: false,
+            alt: false,
+        };
+        let mut res = resource();
+        res.add_accelerator(
+            "shortcuts",
+            &[
+                AcceleratorEntry {
+                    control: true,
+                    shift: true,
+                    ..entry(AcceleratorKey::VirtKey(0x53), 100)
+                },
+                entry(AcceleratorKey::VirtKey(0x70), 101),
+                AcceleratorEntry {
+                    control: true,
+                    ..entry(AcceleratorKey::Char('q'), 102)
+                },
+                AcceleratorEntry {
+                    alt: true,
+                    ..entry(AcceleratorKey::VirtKey(0x73), 103)
+                },
+                entry(AcceleratorKey::Char('?'), 104),
+                entry(AcceleratorKey::Char('"'), 105),
+            ],
+        );
+        assert_golden("accelerators", &res);
+
+        res.add_accelerator(
+            "shortcuts",
+            &[AcceleratorEntry {
+                shift: true,
+                ..entry(AcceleratorKey::Char('a'), 100)
+            }],
+        );
+        let err = res.validate().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Accelerator table shortcuts: Char('a'): SHIFT requires a virtual key"
+        );
+
+        res.add_accelerator(
+            "shortcuts",
+            &[AcceleratorEntry {
+                alt: true,
+                ..entry(AcceleratorKey::Char('a'), 100)
+            }],
+        );
+        let err = res.validate().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Accelerator table shortcuts: Char('a'): ALT requires a virtual key"
+        );
+    }
+
+    #[test]
+    fn golden_string_table() {
+        let mut res = resource();
+        res.set_language(0x0407)
+            .add_string(2, "Datei \"öffnen\"\n")
+            .add_string(1, "Speichern");
+        assert_golden("string_table", &res);
+
+        res.add_string(2, "Schließen");
+        let err = res.validate().unwrap_err();
+        assert_eq!(err.to_string(), "Duplicate string table IDs: 2");
+    }
+
+    #[test]
+    fn primary_icon() {
+        let mut res = resource();
+        res.set_icon_with_id("second.ico", "2")
+            .set_icon_with_id("first.ico", "1")
+            .set_primary_icon("1");
+        res.validate().unwrap();
+        let rc = res.generate_rc_content().unwrap();
+        assert!(rc.find("first.ico").unwrap() < rc.find("second.ico").unwrap());
+        assert_eq!(res.icons().collect::<Vec<_>>(), ["second.ico", "first.ico"]);
+
+        res.set_primary_icon("2");
+        let err = res.validate().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Icon '1' would be used as application icon instead of primary icon '2'"
+        );
+
+        res.set_icon_with_id("named.ico", "APP")
+            .set_primary_icon("1");
+        let err = res.validate().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Icon 'APP' would be used as application icon instead of primary icon '1'"
+        );
+
+        res.set_primary_icon("3");
+        let err = res.validate().unwrap_err();
+        assert_eq!(err.to_string(), "Primary icon name ID '3' was not added");
+    }
+
+    #[test]
+    fn language_str() {
+        assert_eq!(super::language_id("en-US"), Some(0x0409));
+        assert_eq!(super::language_id("de_at"), Some(0x0c07));
+        assert_eq!(super::language_id("en"), Some(0x0009));
+        assert_eq!(super::language_id("FR"), Some(0x000c));
+        assert_eq!(super::language_id("und"), Some(0x0000));
+        assert_eq!(super::language_id("en-XX"), None);
+        assert_eq!(super::language_id("xx"), None);
+
+        let mut res = resource();
+        res.set_language_str("gd-GB").unwrap();
+        assert_eq!(res.language(), 0x0491);
+        let err = res.set_language_str("tlh").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(res.language(), 0x0491);
+    }
+
+    #[test]
+    fn duplicate_image_ids() {
+        let mut res = resource();
+        res.set_icon("a.ico")
+            .set_icon_with_id("c.ico", "2")
+            .add_bitmap("1", "a.bmp")
+            .add_cursor("1", "a.cur");
+        res.validate().unwrap();
+
+        res.set_icon_with_id("b.ico", "1");
+        let err = res.validate().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(
+            err.to_string(),
+            "Duplicate icon name ID '1' (paths a.ico, b.ico)"
+        );
+    }
+
+    #[test]
+    fn version_info_disabled() {
+        let mut res = resource();
+        res.set_version_info_enabled(false)
+            .set_icon("icon.ico")
+            .append_rc_content("1 VERSIONINFO {}");
+        let rc = res.generate_rc_content().unwrap();
+        assert_eq!(rc.matches("VERSIONINFO").count(), 1);
+        assert!(!rc.contains("StringFileInfo"));
+        assert!(rc.contains("1 ICON"));
+    }
+
+    #[test]
+    fn golden_manifest() {
+        let mut res = resource();
+        res.set_manifest(
+            r#"<assembly xmlns="urn:schemas-microsoft-com:asm.v1" manifestVersion="1.0">
+</assembly>"#,
+        )
+        .set_segment_heap(true)
+        .append_rc_content("sample MENU { MENUITEM \"&Soup\", 100 }")
+        .include_rc_file("res\\dialogs.rc");
+        assert_golden("manifest", &res);
+    }
+
+    #[test]
+    fn manifest_id() {
+        let mut res = resource();
+        res.set_manifest("<assembly></assembly>")
+            .set_version_info(VersionInfo::FILETYPE, 0x7);
+        let rc = res.generate_rc_content().unwrap();
+        assert!(rc.contains("\n1 24\n"));
+
+        res.set_version_info(VersionInfo::FILETYPE, 0x2);
+        let rc = res.generate_rc_content().unwrap();
+        assert!(rc.contains("\n2 24\n"));
+
+        res.set_version_info(VersionInfo::FILETYPE, 0x1)
+            .set_crate_type(super::CrateType::DynamicLibrary);
+        let rc = res.generate_rc_content().unwrap();
+        assert!(rc.contains("\n2 24\n"));
+
+        res.set_manifest_id(5);
+        let rc = res.generate_rc_content().unwrap();
+        assert!(rc.contains("\n5 24\n"));
+    }
+
+    #[test]
+    fn manifest_without_filetype() {
+        let mut res = resource();
+        res.set_manifest("<assembly></assembly>")
+            .remove_version_info(VersionInfo::FILETYPE);
+        assert!(res.generate_rc_content().unwrap().contains("\n1 24\n"));
+
+        res.set_version_info_enabled(false);
+        assert!(res.generate_rc_content().unwrap().contains("\n1 24\n"));
+    }
+
+    #[test]
+    fn load_from_toml() {
+        let dir = std::env::temp_dir().join("winres-load-from-toml");
+        let _ = std::fs::create_dir_all(&dir);
+        let toml = dir.join("Shared.toml");
+        std::fs::write(
+            &toml,
+            "[package.metadata.winres]\nProductName = \"Shared\"\n",
+        )
+        .unwrap();
+        let mut res = resource();
+        res.load_from_toml(&toml.to_string_lossy()).unwrap();
+        assert_eq!(res.property("ProductName"), Some("Shared"));
+
+        std::fs::write(
+            &toml,
+            "[package.metadata.winres]\nCompanyName = \"Shared\"\nProductName = 1\n",
+        )
+        .unwrap();
+        let err = res.load_from_toml(&toml.to_string_lossy()).unwrap_err();
+        assert!(err
+            .to_string()
+            .ends_with("package.metadata.winres.ProductName is not a string"));
+        // nothing is applied when a value is invalid
+        assert_eq!(res.property("CompanyName"), None);
+
+        std::fs::write(
+            &toml,
+            "[package.metadata.winres]\nFILEVERSION = \"1.2.3.4\"\nFILEFLAGS = 0x8\n",
+        )
+        .unwrap();
+        res.load_from_toml(&toml.to_string_lossy()).unwrap();
+        assert_eq!(
+            res.version_info(&VersionInfo::FILEVERSION),
+            Some(0x0001000200030004)
+        );
+        assert_eq!(res.version_info(&VersionInfo::FILEFLAGS), Some(0x8));
+
+        std::fs::write(&toml, "[package.metadata.winres]\nFILEOS = \"NT\"\n").unwrap();
+        let err = res.load_from_toml(&toml.to_string_lossy()).unwrap_err();
+        assert!(err
+            .to_string()
+            .ends_with("package.metadata.winres.FILEOS is not a positive integer"));
+
+        std::fs::write(&toml, "[package").unwrap();
+        let err = res.load_from_toml(&toml.to_string_lossy()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        let err = res
+            .load_from_toml(&dir.join("Missing.toml").to_string_lossy())
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn strict_metadata_errors() {
+        let mut res = resource();
+        res.metadata_errors = vec!["package.metadata.winres.FileFlags is not a string".into()];
+        assert!(res.validate().is_ok());
+        res.set_strict(true);
+        assert_eq!(
+            res.validate().unwrap_err().to_string(),
+            "Invalid metadata in Cargo.toml: package.metadata.winres.FileFlags is not a string"
+        );
+    }
+
+    #[test]
+    fn parse_version() {
+        use super::parse_version;
+        assert_eq!(parse_version("1.2.3.4"), Ok(0x0001000200030004));
+        assert_eq!(parse_version("1.2"), Ok(0x0001000200000000));
+        assert_eq!(
+            parse_version("1.2.3.4.5"),
+            Err("\"1.2.3.4.5\" has more than four numbers".to_string())
+        );
+        assert!(parse_version("1.x").is_err());
+        assert!(parse_version("1.65536").is_err());
+    }
+
+    #[test]
+    fn file_version() {
+        let mut res = resource();
+        res.set_file_version_parts(1, 2, 3, 4);
+        assert_eq!(
+            res.version_info(&VersionInfo::FILEVERSION),
+            Some(0x0001000200030004)
+        );
+        res.set_file_version_str("5.6").unwrap();
+        assert_eq!(
+            res.version_info(&VersionInfo::FILEVERSION),
+            Some(0x0005000600000000)
+        );
+        let err = res.set_file_version_str("1.2.3.4.5").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(
+            res.version_info(&VersionInfo::FILEVERSION),
+            Some(0x0005000600000000)
+        );
+    }
+
+    #[test]
+    fn rerun_directives() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let out = std::env::temp_dir().join("winres-rerun");
+        let _ = std::fs::create_dir_all(&out);
+        let mut res = resource();
+        res.set_output_directory(&out.to_string_lossy())
+            .set_icon("icon.ico")
+            .set_icon_with_id("icon.ico", "2")
+            .set_manifest_file("app.manifest")
+            .include_rc_file("dialogs.rc")
+            .add_rcdata("DATA", b"generated")
+            .unwrap();
+        let rerun = |path: PathBuf| format!("cargo:rerun-if-changed={}", path.display());
+        assert_eq!(
+            res.rerun_directives(),
+            vec![
+                rerun(root.join("icon.ico")),
+                rerun(root.join("app.manifest")),
+                rerun(root.join("dialogs.rc")),
+                rerun(root.join("Cargo.toml")),
+            ]
+        );
+    }
+
+    #[test]
+    fn incremental() {
+        let dir = std::env::temp_dir().join("winres-incremental");
+        let _ = std::fs::create_dir_all(&dir);
+        let icon = dir.join("icon.ico");
+        std::fs::write(&icon, b"\0\0\x01\0first").unwrap();
+        let mut res = resource();
+        res.set_output_directory(&dir.to_string_lossy())
+            .set_icon(&icon.to_string_lossy());
+        let rc = res.resource_script().unwrap();
+        let hash = res.input_hash(&rc, "msvc").unwrap();
+        assert_eq!(res.input_hash(&rc, "msvc").unwrap(), hash);
+        assert_ne!(res.input_hash(&rc, "gnu").unwrap(), hash);
+
+        std::fs::write(&icon, b"\0\0\x01\0second").unwrap();
+        assert_ne!(res.input_hash(&rc, "msvc").unwrap(), hash);
+        let hash = res.input_hash(&rc, "msvc").unwrap();
+        res.set_toolkit_path("/other/sdk");
+        assert_ne!(res.input_hash(&rc, "msvc").unwrap(), hash);
+
+        let lib = dir.join("resource.lib");
+        let _ = std::fs::remove_file(&lib);
+        assert!(res.unchanged_output("msvc").is_none());
+        std::fs::write(&lib, b"").unwrap();
+        let output = res.unchanged_output("msvc").unwrap();
+        assert_eq!(output.artifact, Some(lib));
+        assert_eq!(output.directives.len(), 2);
+    }
+
+    #[test]
+    fn golden_version_fields() {
+        let mut res = resource();
+        res.set_version_info(VersionInfo::FILETYPE, 2)
+            .set_file_flags(super::VS_FF_PRIVATEBUILD)
+            .set("PrivateBuild", "built by CI")
+            .set("Comments", "");
+        assert_golden("version_fields", &res);
+    }
+
+    #[test]
+    fn golden_version_fields_removed() {
+        let mut res = resource();
+        res.remove_version_info(VersionInfo::FILEOS)
+            .remove_version_info(VersionInfo::FILETYPE)
+            .remove_version_info(VersionInfo::FILESUBTYPE)
+            .remove_version_info(VersionInfo::FILEFLAGSMASK)
+            .remove_version_info(VersionInfo::FILEFLAGS);
+        assert_golden("version_fields_removed", &res);
+    }
+
+    #[test]
+    fn golden_icon_only() {
+        assert_golden("icon_only", &WindowsResource::icon_only("icon.ico"));
+    }
+
+    #[test]
+    fn icon_from_bytes() {
+        let data: &'static [u8] = include_bytes!("test.ico");
+        let mut res = WindowsResource::new();
+        res.set_output_directory(std::env::temp_dir().to_str().unwrap());
+        res.set_icon_from_bytes(data).unwrap();
+        let path = &res.icons[0].path;
+        assert!(path.ends_with(&format!("icon-{:016x}.ico", super::content_hash(data))));
+        assert_eq!(std::fs::read(path).unwrap(), data);
+        assert_eq!(super::content_hash(b""), 0xcbf29ce484222325);
+    }
+
+    #[test]
+    fn rcdata_from_bytes() {
+        let data = [0u8, 0xff, b'"', b'\n', 0x80];
+        let mut res = resource();
+        res.set_output_directory(std::env::temp_dir().to_str().unwrap());
+        res.add_rcdata("BLOB", b"old").unwrap();
+        res.add_rcdata("BLOB", &data).unwrap();
+        assert_eq!(res.resources.len(), 1);
+        let path = &res.resources[0].path;
+        assert!(path.ends_with(&format!("rcdata-{:016x}.bin", super::content_hash(&data))));
+        assert_eq!(std::fs::read(path).unwrap(), data);
+        let rc = res.generate_resource_string().unwrap();
+        assert!(rc.contains(&format!("BLOB RCDATA \"{}\"", escape_string(path))));
+    }
+
+    #[test]
+    fn icon_validation() {
+        let png = std::env::temp_dir().join("winres-icon-validation.png");
+        std::fs::write(&png, b"\x89PNG\r\n\x1a\n").unwrap();
+        let mut res = resource();
+        res.set_icon("test.ico");
+        assert!(res.check_resource_files().is_ok());
+
+        res.set_icon_with_id(&png.to_string_lossy(), "2")
+            .set_icon_with_id("/nonexistent/icon.ico", "3")
+            .add_bitmap("SPLASH", "test.ico")
+            .add_cursor("DRAG", "test.ico");
+        match res.check_resource_files() {
+            Err(super::WinresError::InvalidResourceFiles(files)) => assert_eq!(
+                files,
+                vec![
+                    (png, "not an ICO file, found a PNG image".to_string()),
+                    (
+                        PathBuf::from("/nonexistent/icon.ico"),
+                        "file not found".to_string()
+                    ),
+                    (
+                        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test.ico"),
+                        "not a BMP file, found an ICO icon".to_string()
+                    ),
+                    (
+                        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test.ico"),
+                        "not a CUR file, found an ICO icon".to_string()
+                    ),
+                ]
+            ),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn include_path_order() {
+        let mut res = WindowsResource::new();
+        res.add_include_path("a")
+            .add_include_path_front("b")
+            .add_include_path("c")
+            .add_include_path_front("d");
+        let dirs = res.include_dirs(vec![PathBuf::from("sdk")]);
+        let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        assert_eq!(
+            dirs,
+            vec![
+                root.join("d"),
+                root.join("b"),
+                root.clone(),
+                root.join("a"),
+                root.join("c"),
+                PathBuf::from("sdk"),
+            ]
+        );
+    }
+
+    #[test]
+    fn strict_properties() {
+        let mut res = resource();
+        res.set("ProdcutName", "typo").set("LegalTrademarks", "TM");
+        assert!(res.validate().is_ok());
+        res.set_strict(true);
+        let err = res.validate().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown version info properties: ProdcutName"
+        );
+        res.properties.remove("ProdcutName");
+        assert!(res.validate().is_ok());
+    }
+
+    #[test]
+    fn manifest_resource() {
+        let mut res = resource();
+        res.set_manifest_file("manifest.xml");
+        let rc = res.generate_resource_string().unwrap();
+        assert!(rc.contains("\n1 24 \""));
+        res.set_manifest_resource(3, 240);
+        let rc = res.generate_resource_string().unwrap();
+        assert!(rc.contains("\n3 240 \""));
+    }
+
+    #[test]
+    fn icon_sizes() {
+        let sizes = super::ico_sizes(include_bytes!("test.ico")).unwrap();
+        assert!(!sizes.is_empty());
+        let mut ico = vec![0, 0, 1, 0, 2, 0];
+        ico.extend_from_slice(&[50, 50, 0, 0, 1, 0, 32, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        ico.extend_from_slice(&[0, 0, 0, 0, 1, 0, 32, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(super::ico_sizes(&ico).unwrap(), vec![(50, 50), (256, 256)]);
+        assert!(super::ico_sizes(b"\x89PNG").is_err());
+    }
+
+    #[test]
+    fn golden_multiple_manifests() {
+        use super::ManifestSource;
+
+        let mut res = resource();
+        res.set_manifest("<assembly>\n</assembly>")
+            .add_manifest(
+                3,
+                ManifestSource::Inline("<assembly>\n<component/>\n</assembly>".to_string()),
+            )
+            .add_manifest(4, ManifestSource::File("component.manifest".to_string()));
+        assert_golden("multiple_manifests", &res);
+    }
+
+    #[test]
+    fn product_version_string() {
+        let mut res = resource();
+        res.set_product_version_string("2024 Edition");
+        let rc = res.generate_resource_string().unwrap();
+        assert!(rc.contains("PRODUCTVERSION 1, 2, 3, 0"));
+        assert!(rc.contains("VALUE \"ProductVersion\", \"2024 Edition\""));
+        assert!(rc.contains("VALUE \"FileVersion\", \"1.2.3\""));
+    }
+
+    #[test]
+    fn compile_all_output_names() {
+        use super::WinresError;
+
+        let err = WindowsResource::compile_all(&[resource(), resource()]).unwrap_err();
+        assert!(
+            matches!(err, WinresError::Io(ref e) if e.kind() == std::io::ErrorKind::InvalidInput)
+        );
+
+        let mut first = resource();
+        first.set_icon("missing.ico").set_output_name("first");
+        let mut second = resource();
+        second.set_icon("missing.ico").set_output_name("second");
+        let err = WindowsResource::compile_all(&[first, second]).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("2 resources failed to compile:\nfirst: "));
+        match err {
+            WinresError::Failed(errors) => {
+                assert_eq!(errors[1].0, "second");
+                assert!(matches!(errors[1].1, WinresError::InvalidResourceFiles(_)));
+            }
+            e => panic!("unexpected error {}", e),
+        }
+    }
+
+    #[test]
+    fn error_conversion() {
+        use super::WinresError;
+
+        let err: WinresError = std::io::Error::new(std::io::ErrorKind::InvalidInput, "bad").into();
+        assert!(matches!(err, WinresError::Io(_)));
+        let err: std::io::Error = err.into();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+        let err: std::io::Error = WinresError::ToolkitNotFound("no rc.exe".to_string()).into();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        assert_eq!(err.to_string(), "no rc.exe");
+
+        let err = WinresError::UnsupportedTarget("wasm32-unknown-unknown".to_string());
+        assert!(err.to_string().ends_with("not for wasm32-unknown-unknown"));
+        // without a build script there is no target_env
+        let err = resource().compiler_command_line().unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Resources can only be compiled for Windows"));
+
+        let err = WinresError::MissingEnvVar("CARGO_PKG_VERSION_MAJOR".to_string());
+        assert_eq!(
+            err.to_string(),
+            "CARGO_PKG_VERSION_MAJOR not set; winres must be called from a build script"
+        );
+    }
+
+    #[test]
+    fn toolkit_candidates() {
+        let dir = std::env::temp_dir().join("winres-toolkit-candidates");
+        let _ = std::fs::create_dir_all(&dir);
+        std::fs::write(dir.join("rc.exe"), b"").unwrap();
+
+        let mut res = resource();
+        res.set_toolkit_path("/nonexistent/sdk");
+        res.add_toolkit_path("/nonexistent/first");
+        let err = res.rc_exe().unwrap_err();
+        assert!(err.to_string().contains("/nonexistent/first"));
+        assert!(err.to_string().contains("/nonexistent/sdk"));
+
+        res.add_toolkit_path(&dir);
+        assert_eq!(res.rc_exe().unwrap(), dir.join("rc.exe"));
+    }
+
+    #[test]
+    fn llvm_rc() {
+        use super::ResourceCompiler;
+
+        let dir = std::env::temp_dir().join("winres-llvm-rc");
+        let _ = std::fs::create_dir_all(&dir);
+        let llvm_rc = dir.join("llvm-rc");
+        std::fs::write(&llvm_rc, b"").unwrap();
+
+        let mut res = resource();
+        res.set_toolkit_path("/nonexistent/sdk")
+            .set_llvm_rc_path(&llvm_rc.to_string_lossy());
+        assert_eq!(res.msvc_resource_compiler(), ResourceCompiler::LlvmRc);
+        res.set_resource_compiler(ResourceCompiler::Rc);
+        assert_eq!(res.msvc_resource_compiler(), ResourceCompiler::Rc);
+
+        let command = res.llvm_rc_command(Path::new("in.rc"), Path::new("out.res"));
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(args[0], "-I");
+        assert_eq!(args[1], env!("CARGO_MANIFEST_DIR"));
+        assert_eq!(args[2..], ["/FO", "out.res", "in.rc"]);
+    }
+
+    #[test]
+    fn include_paths() {
+        let root = env!("CARGO_MANIFEST_DIR");
+        let mut res = resource();
+        res.add_toolkit_include(false)
+            .add_include_path("my headers")
+            .add_include_path_front("/front");
+        let dirs = [
+            PathBuf::from("/front"),
+            PathBuf::from(root),
+            Path::new(root).join("my headers"),
+        ];
+
+        let windres = res.windres_command(Path::new("in.rc"), Path::new("out.o"));
+        let args: Vec<_> = windres
+            .get_args()
+            .take(3)
+            .map(|a| a.to_string_lossy())
+            .collect();
+        let expected: Vec<_> = dirs.iter().map(|d| format!("-I{}", d.display())).collect();
+        assert_eq!(args, expected);
+
+        let rc = res.rc_command(
+            Path::new("rc.exe"),
+            Path::new("in.rc"),
+            Path::new("out.res"),
+        );
+        let args: Vec<_> = rc.get_args().take(3).map(|a| a.to_string_lossy()).collect();
+        let expected: Vec<_> = dirs.iter().map(|d| format!("/I{}", d.display())).collect();
+        assert_eq!(args, expected);
+
+        let llvm_rc = res.llvm_rc_command(Path::new("in.rc"), Path::new("out.res"));
+        let args: Vec<_> = llvm_rc.get_args().skip(1).step_by(2).take(3).collect();
+        assert_eq!(args, dirs);
+    }
+
+    #[test]
+    fn gnu_paths_with_spaces() {
+        let toolkit = std::env::temp_dir().join("winres mingw").join("bin");
+        let _ = std::fs::create_dir_all(&toolkit);
+        std::fs::write(toolkit.join("windres"), b"").unwrap();
+
+        let mut res = resource();
+        res.set_toolkit_path(&toolkit.to_string_lossy())
+            .set_windres_path("windres")
+            .set_ar_path("ar")
+            .add_toolkit_include(false);
+        let output = Path::new("out dir").join("resource.o");
+        let input = std::env::temp_dir().join("my project").join("resource.rc");
+        let windres = res.windres_command(&input, &output);
+        assert_eq!(windres.get_program(), toolkit.join("windres"));
+        assert_eq!(windres.get_current_dir(), None);
+        let args: Vec<_> = windres.get_args().collect();
+        assert_eq!(args[args.len() - 2], input);
+        assert_eq!(
+            args[args.len() - 1],
+            std::env::current_dir().unwrap().join(&output)
+        );
+
+        // ar is not in the toolkit directory, so it is looked up in PATH
+        let ar = res.ar_command(Path::new("libresource.a"), &output);
+        assert_eq!(ar.get_program(), "ar");
+        assert_eq!(ar.get_current_dir(), None);
+    }
+
+    #[test]
+    fn sdk_not_found() {
+        let mut res = resource();
+        res.toolkit_path = PathBuf::new();
+        res.sdk_error = Some("Querying the registry key failed: Access is denied.".to_string());
+        match res.compile_with_toolkit_msvc("resource.rc", ".") {
+            Err(super::WinresError::ToolkitNotFound(msg)) => {
+                assert!(msg.starts_with("No Windows SDK found; set the toolkit path"));
+                assert!(msg.ends_with("Access is denied."));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn sdk_arch() {
+        use super::sdk_arch;
+        assert_eq!(sdk_arch("x86_64"), Some("x64"));
+        assert_eq!(sdk_arch("x86"), Some("x86"));
+        assert_eq!(sdk_arch("aarch64"), Some("arm64"));
+        assert_eq!(sdk_arch("arm64ec"), Some("arm64"));
+        assert_eq!(sdk_arch("wasm32"), None);
+
+        let archs = super::sdk_archs_for("aarch64");
+        assert_eq!(archs[0], "arm64");
+        assert!(archs.len() <= 2);
+        assert_eq!(super::sdk_archs_for("wasm32").len(), 1);
+    }
+
+    #[test]
+    fn gnu_tool_candidates() {
+        use super::gnu_tool_candidates;
+        assert_eq!(
+            gnu_tool_candidates("windres", "x86_64"),
+            ["x86_64-w64-mingw32-windres", "windres", "llvm-windres"]
+        );
+        assert_eq!(
+            gnu_tool_candidates("ar", "x86"),
+            ["i686-w64-mingw32-ar", "ar", "llvm-ar"]
+        );
+        assert_eq!(gnu_tool_candidates("ar", ""), ["ar", "llvm-ar"]);
+    }
+
+    #[test]
+    fn sdk_version() {
+        let sdk = super::Sdk::from_path("/kits/10/bin/10.0.17763.0/x64");
+        assert_eq!(sdk.version(), Some("10.0.17763.0"));
+        assert_eq!(
+            sdk.rc_exe(),
+            Path::new("/kits/10/bin/10.0.17763.0/x64/rc.exe")
+        );
+        assert_eq!(super::Sdk::from_path("/kits/8.1/bin/x86").version(), None);
+
+        let res = WindowsResource::with_sdk(&sdk);
+        assert_eq!(res.toolkit_path, Path::new("/kits/10/bin/10.0.17763.0/x64"));
+    }
+
+    #[test]
+    fn select_sdk_version() {
+        let kits = vec![
+            PathBuf::from("/kits/10/bin/10.0.22621.0/x64"),
+            PathBuf::from("/kits/10/bin/10.0.19041.0/x64"),
+            PathBuf::from("/kits/8.1/bin/x64"),
+        ];
+        assert_eq!(
+            super::select_sdk_version(kits.clone(), "10.0.19041.0").unwrap(),
+            kits[1]
+        );
+        let err = super::select_sdk_version(kits, "10.0.17763.0").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        assert_eq!(
+            err.to_string(),
+            "Windows SDK 10.0.17763.0 is not installed, found: 10.0.19041.0, 10.0.22621.0"
+        );
+    }
+
+    #[test]
+    fn skip_on_non_windows() {
+        let mut res = resource();
+        res.set_log_level(super::LogLevel::Quiet);
+        assert!(!res.skip_target_os("windows").unwrap());
+        assert!(!res.skip_target_os("").unwrap());
+        assert!(res.skip_target_os("linux").unwrap());
+
+        res.set_strict(true);
+        assert!(res.skip_target_os("linux").is_err());
+        res.set_skip_on_non_windows(true);
+        assert!(res.skip_target_os("linux").unwrap());
+        assert!(!res.skip_target_os("windows").unwrap());
     }
-    escaped
-}
 
-fn win_sdk_inlcude_root(path: &Path) -> PathBuf {
-    let mut tools_path = PathBuf::new();
-    let mut iter = path.iter();
-    while let Some(p) = iter.next() {
-        if p == "bin" {
-            let version = iter.next().unwrap();
-            tools_path.push("Include");
-            if version.to_string_lossy().starts_with("10.") {
-                tools_path.push(version);
+    #[test]
+    fn output_name() {
+        let mut res = resource();
+        res.set_output_directory("out").set_output_name("myapp_res");
+        assert_eq!(
+            res.resource_script_path(),
+            Path::new("out").join("myapp_res.rc")
+        );
+        assert_eq!(
+            res.msvc_output("out"),
+            Path::new("out").join("myapp_res.lib")
+        );
+    }
+
+    #[test]
+    fn link_directives() {
+        let mut res = resource();
+        let artifact = Path::new("out/resource.o");
+        assert_eq!(
+            res.link_directives("out", "static", "resource", artifact),
+            vec![
+                "cargo:rustc-link-search=native=out",
+                "cargo:rustc-link-lib=static=resource"
+            ]
+        );
+        res.set_gnu_link_mode(super::GnuLinkMode::DirectObject);
+        assert_eq!(
+            res.link_directives("out", "static", "resource", artifact),
+            vec!["cargo:rustc-link-arg=out/resource.o"]
+        );
+        assert_eq!(
+            res.link_directives("out", "dylib", "resource", Path::new("out/resource.lib")),
+            vec![
+                "cargo:rustc-link-search=native=out",
+                "cargo:rustc-link-lib=dylib=resource"
+            ]
+        );
+        res.set_gnu_link_mode(super::GnuLinkMode::StaticArchive)
+            .set_prefer_native_res(true);
+        assert_eq!(
+            res.link_directives("out", "static", "resource", artifact),
+            vec!["cargo:rustc-link-arg=out/resource.o"]
+        );
+        res.set_crate_type(super::CrateType::StaticLibrary);
+        assert!(res
+            .link_directives("out", "static", "resource", artifact)
+            .is_empty());
+    }
+
+    #[test]
+    fn forced_empty_properties() {
+        let mut res = resource();
+        res.set("Comments", "")
+            .set("LegalTrademarks", "")
+            .force_emit_property("Comments")
+            .force_emit_property("PrivateBuild");
+        let rc = res.generate_resource_string().unwrap();
+        assert!(rc.contains("VALUE \"Comments\", \"\"\n"));
+        assert!(rc.contains("VALUE \"PrivateBuild\", \"\"\n"));
+        assert!(!rc.contains("LegalTrademarks"));
+    }
+
+    #[test]
+    fn res_parsing() {
+        use super::{parse_res, ResEntry, ResName};
+
+        fn entry(res: &mut Vec<u8>, type_id: &[u8], name_id: &[u8], data: &[u8]) {
+            let header_size = 8 + type_id.len() + name_id.len() + 16;
+            res.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            res.extend_from_slice(&(header_size as u32).to_le_bytes());
+            res.extend_from_slice(type_id);
+            res.extend_from_slice(name_id);
+            // DataVersion, MemoryFlags, LanguageId 0x0409, Version, Characteristics
+            res.extend_from_slice(&[0, 0, 0, 0, 0x30, 0, 0x09, 0x04]);
+            res.extend_from_slice(&[0; 8]);
+            res.extend_from_slice(data);
+            while res.len() & 3 != 0 {
+                res.push(0);
             }
-            break;
-        } else {
-            tools_path.push(p);
         }
+
+        let mut res = Vec::new();
+        entry(&mut res, &[0xff, 0xff, 0, 0], &[0xff, 0xff, 0, 0], &[]);
+        // RT_RCDATA, name "AB" padded to 4 bytes
+        entry(
+            &mut res,
+            &[0xff, 0xff, 10, 0],
+            &[b'A', 0, b'B', 0, 0, 0, 0, 0],
+            b"xyz",
+        );
+        entry(
+            &mut res,
+            &[0xff, 0xff, 24, 0],
+            &[0xff, 0xff, 1, 0],
+            b"<assembly/>",
+        );
+
+        let entries = parse_res(&res).unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                ResEntry {
+                    type_id: ResName::Id(10),
+                    name_id: ResName::Name(vec![0x41, 0x42, 0]),
+                    language: 0x0409,
+                    data: b"xyz".to_vec(),
+                },
+                ResEntry {
+                    type_id: ResName::Id(24),
+                    name_id: ResName::Id(1),
+                    language: 0x0409,
+                    data: b"<assembly/>".to_vec(),
+                },
+            ]
+        );
+        assert!(parse_res(&res[..res.len() - 8]).is_err());
     }
 
-    tools_path
-}
+    #[test]
+    fn patch_file_date() {
+        use super::patch_file_date;
 
-#[cfg(test)]
-mod tests {
-    use super::escape_string;
-    use super::win_sdk_inlcude_root;
+        // wLength, wValueLength, wType, the key, padding and VS_FIXEDFILEINFO
+        let mut res = vec![0x5c, 0, 0x34, 0, 0, 0];
+        res.extend(
+            "VS_VERSION_INFO\0"
+                .encode_utf16()
+                .flat_map(u16::to_le_bytes),
+        );
+        res.extend_from_slice(&[0, 0, 0xbd, 0x04, 0xef, 0xfe]);
+        res.extend_from_slice(&[0; 48]);
+        let start = res.len() - 52;
+
+        assert!(patch_file_date(&mut res, 0x0102_0304_0506_0708));
+        assert!(res[start + 4..start + 44].iter().all(|&b| b == 0));
+        assert_eq!(res[start + 44..], [4, 3, 2, 1, 8, 7, 6, 5]);
+
+        assert!(!patch_file_date(&mut res[..start + 40], 1));
+        assert!(!patch_file_date(&mut [0; 16], 1));
+    }
+
+    #[test]
+    fn command_line_quoting() {
+        let mut command = std::process::Command::new("C:\\Program Files\\rc.exe");
+        command.arg("/fooutput.res").arg("my resource.rc").arg("");
+        assert_eq!(
+            super::command_line(&command),
+            "\"C:\\Program Files\\rc.exe\" /fooutput.res \"my resource.rc\" \"\""
+        );
+    }
+
+    #[test]
+    fn golden_language_strings() {
+        let mut res = resource();
+        res.set_language(0x0409)
+            .add_language_strings(0x0407, 0x04e4, &[("ProductName", "Goldig")])
+            .add_language_strings(0x040c, 0x04b0, &[("ProductName", "Doré")]);
+        assert_golden("language_strings", &res);
+    }
+
+    #[test]
+    fn codepage() {
+        let mut res = resource();
+        res.set_language(0x0409).set_codepage(0x04e4);
+        let rc = res.generate_rc_content().unwrap();
+        assert!(rc.contains("BLOCK \"040904e4\""));
+        assert!(rc.contains("VALUE \"Translation\", 0x409, 0x04e4"));
+        assert!(!rc.contains("04b0"));
+    }
+
+    #[test]
+    fn prerelease_numbers() {
+        use super::prerelease_number;
+        assert_eq!(prerelease_number("beta.4"), 4);
+        assert_eq!(prerelease_number("rc.2.alpha"), 2);
+        assert_eq!(prerelease_number("beta"), 0);
+        assert_eq!(prerelease_number(""), 0);
+        assert_eq!(prerelease_number("99999"), 0);
+    }
+
+    #[test]
+    fn getters() {
+        let mut res = resource();
+        res.set_language(0x0407)
+            .set_icon("icon.ico")
+            .set_icon_with_id("other.ico", "2")
+            .remove_version_info(VersionInfo::FILEOS);
+        assert_eq!(res.property("ProductName"), Some("Golden"));
+        assert_eq!(res.property("Comments"), None);
+        assert_eq!(
+            res.version_info(&VersionInfo::FILEVERSION),
+            Some(pack_version(1, 2, 3, 0))
+        );
+        assert_eq!(res.version_info(&VersionInfo::FILEOS), None);
+        assert_eq!(res.language(), 0x0407);
+        assert_eq!(res.icons().collect::<Vec<_>>(), ["icon.ico", "other.ico"]);
+    }
+
+    #[test]
+    fn version_build() {
+        let mut res = resource();
+        res.set_version_build(42)
+            .remove_version_info(VersionInfo::PRODUCTVERSION);
+        assert_eq!(
+            res.version_info[&VersionInfo::FILEVERSION],
+            pack_version(1, 2, 3, 42)
+        );
+        assert!(!res.version_info.contains_key(&VersionInfo::PRODUCTVERSION));
+    }
+
+    #[test]
+    fn dry_run() {
+        let dir = std::env::temp_dir().join("winres-dry-run");
+        let _ = std::fs::create_dir_all(&dir);
+        let mut res = resource();
+        res.set_output_directory(&dir.to_string_lossy())
+            .set_toolkit_path("/nonexistent/sdk")
+            .set_dry_run(true);
+        let output = res.compile_silent().unwrap();
+        assert_eq!(output.artifact, None);
+        assert!(output.directives.is_empty());
+        let rc = std::fs::read_to_string(dir.join("resource.rc")).unwrap();
+        assert!(rc.contains("VALUE \"ProductName\", \"Golden\""));
+    }
+
+    #[test]
+    fn manifest_external() {
+        let dir = std::env::temp_dir().join("winres-manifest-external");
+        let _ = std::fs::create_dir_all(&dir);
+        let mut res = resource();
+        res.set_output_directory(&dir.to_string_lossy())
+            .set("OriginalFilename", "golden.exe")
+            .set_requested_execution_level(super::ExecutionLevel::AsInvoker)
+            .set_manifest_external(true);
+        assert!(!res.generate_rc_content().unwrap().contains(" 24\n"));
+        res.resource_script().unwrap();
+        let xml = std::fs::read_to_string(dir.join("golden.exe.manifest")).unwrap();
+        assert!(xml.contains("level=\"asInvoker\""));
+
+        res.set_manifest_external(false);
+        assert!(res.generate_rc_content().unwrap().contains("1 24\n"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn compiler_error_status() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join("winres-compiler-error");
+        let _ = std::fs::create_dir_all(&dir);
+        let windres = dir.join("windres");
+        std::fs::write(
+            &windres,
+            "#!/bin/sh\necho 'resource.rc:3: syntax error' >&2\nexit 3\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&windres, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut res = resource();
+        res.set_output_directory(&dir.to_string_lossy())
+            .set_windres_path(&windres.to_string_lossy());
+        let message = res.lint_with_windres().unwrap_err().to_string();
+        assert!(message.starts_with("Could not compile resource file (exit status: 3)"));
+        assert!(message.contains("syntax error"));
+    }
+
+    #[test]
+    fn resolve_compiler() {
+        use super::{CompilerKind, WinresError};
+
+        let dir = std::env::temp_dir().join("winres-resolve-compiler");
+        let _ = std::fs::create_dir_all(&dir);
+        std::fs::write(dir.join("rc.exe"), b"").unwrap();
+        std::fs::write(dir.join("windres.exe"), b"").unwrap();
+        let mut res = resource();
+        res.set_toolkit_path(&dir.to_string_lossy())
+            .set_windres_path("windres.exe");
+
+        let rc = res.resolve_compiler_for("msvc").unwrap();
+        assert_eq!(rc.kind, CompilerKind::Rc);
+        assert_eq!(rc.path, dir.join("rc.exe"));
+        let windres = res.resolve_compiler_for("gnu").unwrap();
+        assert_eq!(windres.kind, CompilerKind::Windres);
+        assert!(windres.path.ends_with("windres.exe"));
+        assert!(matches!(
+            res.resolve_compiler_for("musl"),
+            Err(WinresError::UnsupportedTarget(_))
+        ));
+
+        res.set_toolkit_path("/nonexistent/sdk")
+            .set_resource_compiler(super::ResourceCompiler::Rc);
+        assert!(matches!(
+            res.resolve_compiler_for("msvc"),
+            Err(WinresError::ToolkitNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn rc_encoding_utf16() {
+        let path = std::env::temp_dir().join("winres-utf16.rc");
+        let mut res = resource();
+        res.set("FileDescription", "⛄❤☕")
+            .set_rc_encoding(super::RcEncoding::Utf16Le);
+        res.write_resource_file(&path).unwrap();
+        let data = std::fs::read(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(&data[..2], &[0xff, 0xfe]);
+        let units: Vec<u16> = data[2..]
+            .chunks(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        let rc = String::from_utf16(&units).unwrap();
+        assert_eq!(rc, res.generate_rc_content().unwrap());
+        assert!(rc.contains("VALUE \"FileDescription\", \"⛄❤☕\""));
+        assert!(!rc.contains("#pragma code_page"));
+    }
+
+    #[test]
+    fn rc_transform() {
+        let path = std::env::temp_dir().join("winres-rc-transform.rc");
+        let mut res = resource();
+        res.set_rc_transform(|rc| rc.replace("\"Golden\"", "\"Transformed\""));
+        assert!(res
+            .generate_rc_content()
+            .unwrap()
+            .contains("VALUE \"ProductName\", \"Transformed\""));
+        res.write_resource_file(&path).unwrap();
+        let rc = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert!(rc.contains("VALUE \"ProductName\", \"Transformed\""));
+    }
 
     #[test]
     fn string_escaping() {
@@ -854,6 +6825,477 @@ mod tests {
             &escape_string(r"C:\Program Files\Foobar"),
             r"C:\\Program Files\\Foobar"
         );
+        assert_eq!(&escape_string("a\0b\x07c"), r"a\000b\007c");
+        assert_eq!(&escape_string("\x0b1\x7f"), r"\0131\177");
+    }
+
+    #[test]
+    fn private_and_special_build() {
+        let mut res = resource();
+        res.set_file_flags(super::VS_FF_PRERELEASE)
+            .set_private_build("built by CI")
+            .set_special_build("with logging");
+        assert_eq!(res.property("PrivateBuild"), Some("built by CI"));
+        assert_eq!(res.property("SpecialBuild"), Some("with logging"));
+        assert_eq!(
+            res.version_info(&VersionInfo::FILEFLAGS),
+            Some(super::VS_FF_PRERELEASE | super::VS_FF_PRIVATEBUILD | super::VS_FF_SPECIALBUILD)
+        );
+        let rc = res.generate_rc_content().unwrap();
+        assert!(rc.contains("FILEFLAGS 0x2a\n"));
+    }
+
+    #[test]
+    fn custom_resources() {
+        use super::ResourceType;
+
+        let mut res = resource();
+        res.add_resource(ResourceType::TypeLib, "1", "/build/app.tlb")
+            .add_resource(ResourceType::Html, "INDEX", "/web/index.html")
+            .add_resource(ResourceType::Custom(256), "INDEX", "/web/old.bin")
+            .add_resource(ResourceType::Custom(256), "INDEX", "/web/data \"1\".bin");
+        let rc = res.generate_rc_content().unwrap();
+        assert!(rc.contains("1 TYPELIB \"/build/app.tlb\"\n"));
+        assert!(rc.contains("INDEX HTML \"/web/index.html\"\n"));
+        assert!(rc.contains("INDEX 256 \"/web/data \"\"1\"\".bin\"\n"));
+        assert!(!rc.contains("old.bin"));
+    }
+
+    #[test]
+    fn embed_license() {
+        let mut res = resource();
+        res.embed_license_from("", "", Path::new("/project"));
+        assert_eq!(res.property("License"), None);
+        assert!(res.resources.is_empty());
+
+        res.add_resource(super::ResourceType::RcData, "LICENSE", "/old/LICENSE");
+        res.embed_license_from("MIT OR Apache-2.0", "LICENSE.txt", Path::new("/project"));
+        assert_eq!(res.property("License"), Some("MIT OR Apache-2.0"));
+        let rc = res.generate_rc_content().unwrap();
+        assert!(rc.contains("VALUE \"License\", \"MIT OR Apache-2.0\""));
+        assert!(rc.contains("LICENSE RCDATA \"/project/LICENSE.txt\"\n"));
+        assert!(!rc.contains("/old/LICENSE"));
+    }
+
+    #[test]
+    fn html_resource() {
+        let mut res = resource();
+        res.set_icon("test.ico")
+            .add_html("INDEX.HTML", "test.ico")
+            .add_html("APP.JS", "/nonexistent/app.js");
+        let rc = res.generate_rc_content().unwrap();
+        assert!(rc.contains("INDEX.HTML HTML \""));
+        assert!(res
+            .input_paths()
+            .contains(&PathBuf::from("/nonexistent/app.js")));
+        match res.check_resource_files() {
+            Err(super::WinresError::InvalidResourceFiles(files)) => assert_eq!(
+                files,
+                vec![(
+                    PathBuf::from("/nonexistent/app.js"),
+                    "file not found".to_string()
+                )]
+            ),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn wide_string_escaping() {
+        assert_eq!(
+            &super::escape_wide_string("Snow ⛄ \"\u{1f600}\""),
+            r#"Snow ⛄ ""\xd83d\xde00"""#
+        );
+        assert_eq!(
+            &super::escape_wide_string("\u{1f600}1a x"),
+            r"\xd83d\xde00\x0031\x0061 x"
+        );
+
+        let mut res = resource();
+        res.set("FileDescription", "Smile \u{1f600}")
+            .set("Comments", "⛄❤☕")
+            .add_string(1, "\u{1f600}");
+        let rc = res.generate_rc_content().unwrap();
+        assert!(rc.contains(r#"VALUE "FileDescription", L"Smile \xd83d\xde00""#));
+        assert!(rc.contains(r#"VALUE "Comments", "⛄❤☕""#));
+        assert!(rc.contains(r#"1, L"\xd83d\xde00""#));
+
+        res.set_rc_encoding(super::RcEncoding::Utf16Le);
+        let rc = res.generate_rc_content().unwrap();
+        assert!(rc.contains("VALUE \"FileDescription\", \"Smile \u{1f600}\""));
+    }
+
+    #[test]
+    fn version_packing() {
+        assert_eq!(pack_version(1, 0, 0, 0), 0x0001000000000000);
+        assert_eq!(pack_version(0xffff, 2, 3, 0xfffe), 0xffff00020003fffe);
+        assert_eq!(unpack_version(0x0001000200030004), (1, 2, 3, 4));
+        assert_eq!(unpack_version(pack_version(7, 8, 9, 10)), (7, 8, 9, 10));
+    }
+
+    #[test]
+    fn rc_diagnostics() {
+        let d = parse_rc_line(r"C:\res\resource.rc(12) : error RC2135 : file not found: icon.ico")
+            .unwrap();
+        assert_eq!(d.severity, Severity::Error);
+        assert_eq!(d.file.as_deref(), Some(r"C:\res\resource.rc"));
+        assert_eq!(d.line, Some(12));
+        assert_eq!(d.message, "file not found: icon.ico");
+
+        let d = parse_rc_line("resource.rc(3) : warning RC4005: X : redefinition").unwrap();
+        assert_eq!(d.severity, Severity::Warning);
+        assert_eq!(d.message, "X : redefinition");
+
+        let d = parse_rc_line("fatal error RC1110: could not open resource.rc").unwrap();
+        assert_eq!(d.file, None);
+        assert_eq!(d.message, "could not open resource.rc");
+
+        assert!(parse_rc_line("Microsoft (R) Windows (R) Resource Compiler").is_none());
+    }
+
+    #[test]
+    fn windres_diagnostics() {
+        let d = parse_windres_line("windres: resource.rc:3: syntax error").unwrap();
+        assert_eq!(d.severity, Severity::Error);
+        assert_eq!(d.file.as_deref(), Some("resource.rc"));
+        assert_eq!(d.line, Some(3));
+        assert_eq!(d.message, "syntax error");
+
+        let d = parse_windres_line(r#"C:\res\resource.rc:5:9: warning: "X" redefined"#).unwrap();
+        assert_eq!(d.severity, Severity::Warning);
+        assert_eq!(d.file.as_deref(), Some(r"C:\res\resource.rc"));
+        assert_eq!(d.line, Some(5));
+        assert_eq!(d.message, r#""X" redefined"#);
+
+        let d =
+            parse_windres_line("x86_64-w64-mingw32-windres: can't open icon file `a.ico'").unwrap();
+        assert_eq!(d.file, None);
+        assert_eq!(d.message, "can't open icon file `a.ico'");
+
+        assert!(parse_windres_line("    5 | #include <foo.h>").is_none());
+    }
+
+    #[test]
+    fn warnings_as_errors() {
+        #[cfg(unix)]
+        use std::os::unix::process::ExitStatusExt;
+        #[cfg(windows)]
+        use std::os::windows::process::ExitStatusExt;
+
+        let output = std::process::Output {
+            status: std::process::ExitStatus::from_raw(0),
+            stdout: Vec::new(),
+            stderr: b"resource.rc:2: warning: duplicate resource ID".to_vec(),
+        };
+        let diagnostics: Vec<_> = String::from_utf8_lossy(&output.stderr)
+            .lines()
+            .filter_map(parse_windres_line)
+            .collect();
+        let mut res = resource();
+        assert!(!res.compiler_failed(&output, &diagnostics));
+        res.set_warnings_as_errors(true);
+        assert!(res.compiler_failed(&output, &diagnostics));
+        assert!(!res.compiler_failed(&output, &[]));
+        assert_eq!(
+            super::WinresError::compiler(output, diagnostics).to_string(),
+            "Resource compiler reported warnings, which are treated as errors\nresource.rc(2): warning: duplicate resource ID"
+        );
+    }
+
+    #[cfg(feature = "conversion")]
+    fn png(size: u32) -> Vec<u8> {
+        let mut png = super::PNG_MAGIC.to_vec();
+        png.extend_from_slice(&[0, 0, 0, 13]);
+        png.extend_from_slice(b"IHDR");
+        png.extend_from_slice(&size.to_be_bytes());
+        png.extend_from_slice(&size.to_be_bytes());
+        png.extend_from_slice(&[8, 6, 0, 0, 0, 0, 0, 0, 0]);
+        png
+    }
+
+    #[cfg(feature = "conversion")]
+    fn bmp(size: u32) -> Vec<u8> {
+        let pixels = (size * size * 4) as usize;
+        let mut bmp = b"BM".to_vec();
+        bmp.extend_from_slice(&(54 + pixels as u32).to_le_bytes());
+        bmp.extend_from_slice(&[0, 0, 0, 0, 54, 0, 0, 0, 40, 0, 0, 0]);
+        bmp.extend_from_slice(&size.to_le_bytes());
+        bmp.extend_from_slice(&size.to_le_bytes());
+        bmp.extend_from_slice(&[1, 0, 32, 0]);
+        bmp.resize(54 + pixels, 0);
+        bmp
+    }
+
+    #[cfg(feature = "conversion")]
+    #[test]
+    fn ico_from_images() {
+        use super::{write_ico, IcoImage};
+
+        let images = vec![
+            IcoImage::from_bmp(&bmp(16)).unwrap(),
+            IcoImage::from_png(png(256)).unwrap(),
+        ];
+        assert_eq!(images[0].bit_count, 32);
+        // 40 byte header, pixels and AND mask
+        assert_eq!(images[0].data.len(), 40 + 16 * 16 * 4 + 16 * 4);
+        assert_eq!(images[1].width, 256);
+
+        let ico = write_ico(&images);
+        assert_eq!(&ico[0..6], &[0, 0, 1, 0, 2, 0]);
+        // first entry: 16x16, data after both directory entries
+        assert_eq!(&ico[6..8], &[16, 16]);
+        assert_eq!(&ico[18..22], &38u32.to_le_bytes());
+        // second entry: 256 is stored as 0
+        assert_eq!(&ico[22..24], &[0, 0]);
+        assert_eq!(&ico[ico.len() - png(256).len()..], &png(256)[..]);
+        // the height of the DIB counts XOR and AND mask
+        assert_eq!(&ico[38 + 8..38 + 12], &32u32.to_le_bytes());
+    }
+
+    #[cfg(feature = "conversion")]
+    #[test]
+    fn bmp_header_sizes() {
+        use super::IcoImage;
+
+        let header = |width: u32, height: u32| {
+            let mut bmp = bmp(16);
+            bmp[18..22].copy_from_slice(&width.to_le_bytes());
+            bmp[22..26].copy_from_slice(&height.to_le_bytes());
+            IcoImage::from_bmp(&bmp).unwrap_err().to_string()
+        };
+        assert_eq!(header(u32::MAX, i32::MAX as u32), "BMP image is too large");
+        assert_eq!(header(0x10000, 0x10000), "BMP image is truncated");
+
+        let mut bmp = bmp(16);
+        bmp[10..14].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(
+            IcoImage::from_bmp(&bmp).unwrap_err().to_string(),
+            "BMP image is truncated"
+        );
+    }
+
+    #[cfg(feature = "conversion")]
+    #[test]
+    fn ico_filtered() {
+        use super::{read_ico, write_ico, IcoImage};
+
+        let ico = write_ico(&[
+            IcoImage::from_bmp(&bmp(16)).unwrap(),
+            IcoImage::from_bmp(&bmp(32)).unwrap(),
+            IcoImage::from_png(png(256)).unwrap(),
+        ]);
+        let images = read_ico(&ico).unwrap();
+        assert_eq!(images.len(), 3);
+        assert_eq!(images[2].width, 256);
+        assert_eq!(images[2].data, png(256));
+        assert_eq!(write_ico(&images), ico);
+
+        let path = std::env::temp_dir().join("winres-filtered-source.ico");
+        std::fs::write(&path, &ico).unwrap();
+        let mut res = resource();
+        res.set_output_directory(&std::env::temp_dir().to_string_lossy());
+        res.set_icon_filtered(&path.to_string_lossy(), &[16, 256], "filtered")
+            .unwrap();
+        let filtered = std::fs::read(std::env::temp_dir().join("icon_filtered.ico")).unwrap();
+        assert_eq!(
+            super::ico_sizes(&filtered).unwrap(),
+            vec![(16, 16), (256, 256)]
+        );
+        assert!(res
+            .set_icon_filtered(&path.to_string_lossy(), &[48], "filtered")
+            .is_err());
+    }
+
+    #[cfg(feature = "conversion")]
+    #[test]
+    fn icon_group() {
+        use super::{write_ico, IcoImage};
+
+        let dir = std::env::temp_dir();
+        let ico = dir.join("winres-group-small.ico");
+        std::fs::write(
+            &ico,
+            write_ico(&[
+                IcoImage::from_bmp(&bmp(16)).unwrap(),
+                IcoImage::from_bmp(&bmp(32)).unwrap(),
+            ]),
+        )
+        .unwrap();
+        let large = dir.join("winres-group-256.png");
+        std::fs::write(&large, png(256)).unwrap();
+
+        let mut res = resource();
+        res.set_output_directory(&dir.to_string_lossy());
+        res.add_icon_group("group", &[&ico.to_string_lossy(), &large.to_string_lossy()])
+            .unwrap();
+        let group = std::fs::read(dir.join("icon_group.ico")).unwrap();
+        assert_eq!(
+            super::ico_sizes(&group).unwrap(),
+            vec![(16, 16), (32, 32), (256, 256)]
+        );
+        assert_eq!(res.icons().count(), 1);
+
+        let err = res
+            .add_icon_group("twice", &[&ico.to_string_lossy(), &ico.to_string_lossy()])
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("already has a 16x16 image"));
+    }
+
+    fn inline(source: super::ManifestSource) -> String {
+        match source {
+            super::ManifestSource::Inline(xml) => xml,
+            super::ManifestSource::File(file) => panic!("{} is not inline", file),
+        }
+    }
+
+    #[test]
+    fn manifest_settings() {
+        let mut res = super::WindowsResource::new();
+        res.set_segment_heap(true).set_gdi_scaling(true);
+        let xml = inline(res.primary_manifest().unwrap().unwrap());
+        assert!(xml.contains("<assembly xmlns=\"urn:schemas-microsoft-com:asm.v1\""));
+        assert!(xml.contains(
+            "<heapType xmlns=\"http://schemas.microsoft.com/SMI/2020/WindowsSettings\">SegmentHeap</heapType>"
+        ));
+
+        res.set_segment_heap(false);
+        let xml = inline(res.primary_manifest().unwrap().unwrap());
+        assert!(!xml.contains("heapType"));
+        assert!(xml.contains("<gdiScaling"));
+
+        res.set_manifest("<assembly>\n<application><windowsSettings>\n<foo/>\n</windowsSettings></application>\n</assembly>");
+        let xml = inline(res.primary_manifest().unwrap().unwrap());
+        assert!(xml.contains("<foo/>\n    <gdiScaling"));
+        assert_eq!(xml.matches("<windowsSettings>").count(), 1);
+
+        res.set_manifest_file("manifest.xml");
+        assert!(res.primary_manifest().is_err());
+    }
+
+    #[test]
+    fn execution_level() {
+        let mut res = super::WindowsResource::new();
+        res.set_requested_execution_level(super::ExecutionLevel::RequireAdministrator);
+        let xml = inline(res.primary_manifest().unwrap().unwrap());
+        assert!(xml.starts_with("<?xml version=\"1.0\""));
+        assert!(xml.contains(
+            "<trustInfo xmlns=\"urn:schemas-microsoft-com:asm.v3\">\n<security>\n<requestedPrivileges>\n    <requestedExecutionLevel level=\"requireAdministrator\" uiAccess=\"false\"/>\n"
+        ));
+        assert!(xml.ends_with("</trustInfo>\n</assembly>\n"));
+
+        res.set_manifest("<assembly>\n<compatibility/>\n</assembly>");
+        let xml = inline(res.primary_manifest().unwrap().unwrap());
+        assert!(xml.starts_with("<assembly>\n<compatibility/>\n<trustInfo"));
+
+        res.set_manifest("<assembly><requestedExecutionLevel level=\"asInvoker\"/></assembly>");
+        assert!(res.primary_manifest().is_err());
+    }
+
+    #[test]
+    fn dpi_awareness_with_execution_level() {
+        let mut res = super::WindowsResource::new();
+        res.set_dpi_awareness(super::DpiAwareness::System)
+            .set_requested_execution_level(super::ExecutionLevel::AsInvoker);
+        let xml = inline(res.primary_manifest().unwrap().unwrap());
+        assert_eq!(xml.matches("<assembly ").count(), 1);
+        assert!(xml.contains("level=\"asInvoker\""));
+        assert!(
+            xml.contains(">System</dpiAwareness>\n</windowsSettings>\n</application>\n</assembly>")
+        );
+
+        res.set_manifest("<assembly>\n<application><windowsSettings>\n<dpiAware>true</dpiAware>\n</windowsSettings></application>\n</assembly>");
+        let err = res.primary_manifest().unwrap_err();
+        assert!(err.to_string().contains("<dpiAware>"));
+    }
+
+    #[test]
+    fn long_paths_and_code_page() {
+        let mut res = super::WindowsResource::new();
+        res.set_long_path_aware(true)
+            .set_active_code_page(super::ActiveCodePage::Utf8)
+            .set_dpi_awareness(super::DpiAwareness::System);
+        let xml = inline(res.primary_manifest().unwrap().unwrap());
+        assert_eq!(xml.matches("<windowsSettings>").count(), 1);
+        assert!(xml.contains(
+            "<windowsSettings>\n    <longPathAware xmlns=\"http://schemas.microsoft.com/SMI/2016/WindowsSettings\">true</longPathAware>\n    <activeCodePage xmlns=\"http://schemas.microsoft.com/SMI/2019/WindowsSettings\">UTF-8</activeCodePage>\n"
+        ));
+
+        res.set_long_path_aware(false)
+            .set_active_code_page(super::ActiveCodePage::Legacy);
+        let xml = inline(res.primary_manifest().unwrap().unwrap());
+        assert!(!xml.contains("longPathAware"));
+        assert!(xml.contains(">Legacy</activeCodePage>"));
+
+        res.set_manifest_file("app.manifest");
+        assert!(res.primary_manifest().is_err());
+        res.set_manifest("<assembly>\n<activeCodePage>UTF-8</activeCodePage>\n</assembly>");
+        assert!(res.primary_manifest().is_err());
+    }
+
+    #[test]
+    fn dependent_assemblies() {
+        let mut res = super::WindowsResource::new();
+        res.enable_common_controls_v6()
+            .set_requested_execution_level(super::ExecutionLevel::AsInvoker)
+            .set_gdi_scaling(true);
+        let xml = inline(res.primary_manifest().unwrap().unwrap());
+        assert_eq!(xml.matches("<assembly ").count(), 1);
+        assert!(xml.contains(
+            "</trustInfo>\n<dependency>\n<dependentAssembly>\n    <assemblyIdentity type=\"win32\" name=\"Microsoft.Windows.Common-Controls\" version=\"6.0.0.0\" processorArchitecture=\"*\" publicKeyToken=\"6595b64144ccf1df\" language=\"*\"/>\n</dependentAssembly>\n</dependency>\n<application"
+        ));
+
+        res.add_dependent_assembly(
+            "Microsoft.Windows.Common-Controls",
+            "6.0.1.0",
+            "6595b64144ccf1df",
+        );
+        let xml = inline(res.primary_manifest().unwrap().unwrap());
+        assert_eq!(xml.matches("<dependency>").count(), 1);
+        assert!(xml.contains("version=\"6.0.1.0\""));
+
+        res.set_manifest("<assembly><dependency><dependentAssembly><assemblyIdentity name=\"Microsoft.Windows.Common-Controls\"/></dependentAssembly></dependency></assembly>");
+        let err = res.primary_manifest().unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("already depends on Microsoft.Windows.Common-Controls"));
+    }
+
+    #[test]
+    fn manifest_file_inline() {
+        let path = std::env::temp_dir().join("winres-inline.manifest");
+        std::fs::write(&path, "<assembly>\n</assembly>\n").unwrap();
+        let mut res = super::WindowsResource::new();
+        res.set_manifest_file_inline(&path.to_string_lossy())
+            .unwrap()
+            .set_gdi_scaling(true);
+        let xml = inline(res.primary_manifest().unwrap().unwrap());
+        assert!(xml.starts_with("<assembly>\n<application"));
+
+        let err = res
+            .set_manifest_file_inline("/nonexistent/app.manifest")
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        assert!(err.to_string().contains("/nonexistent/app.manifest"));
+    }
+
+    #[test]
+    fn dpi_awareness_marker() {
+        let mut res = super::WindowsResource::new();
+        res.set_dpi_awareness(super::DpiAwareness::PerMonitorV2)
+            .set_dpi_awareness_marker("DPI_AWARENESS");
+        let rc = res.generate_resource_string().unwrap();
+        assert!(rc.contains("<dpiAware xmlns=\"\"http://schemas.microsoft.com/SMI/2005/WindowsSettings\"\">true/pm</dpiAware>"));
+        assert!(rc.contains("<dpiAwareness xmlns=\"\"http://schemas.microsoft.com/SMI/2016/WindowsSettings\"\">PerMonitorV2,PerMonitor</dpiAwareness>"));
+        assert!(rc.contains("DPI_AWARENESS RCDATA { \"permonitorv2\\0\" }"));
+    }
+
+    #[test]
+    fn app_user_model_id() {
+        let mut res = resource();
+        res.set_app_user_model_id("Contoso.Golden.App");
+        let rc = res.generate_resource_string().unwrap();
+        assert!(rc.contains("APP_USER_MODEL_ID RCDATA { L\"Contoso.Golden.App\\0\" }"));
     }
 
     #[test]